@@ -0,0 +1,397 @@
+use crate::export::{self, Destination};
+use crate::site::{load_profiles, names, shrink};
+use crate::visiting::{self, ShootingTime};
+use crate::yearbook::{self, escape};
+use crate::{decoding, terms, State};
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::Path;
+use time::Date;
+use toml::value::Table;
+
+/// 书中图片的最长边
+const MAX_SIDE: u32 = 1000;
+/// 每位同学的章节最多附上几张照片
+const PHOTOS_PER_PERSON: usize = 4;
+const OTHER_TIME: &str = "其他时间";
+const CLASSMATES: &str = "同学们";
+
+/// 书里的一章，`file` 为 OEBPS 下的文件名。
+struct Chapter {
+    file: String,
+    title: String,
+    body: String,
+}
+
+/// 书里的一张图片。
+struct Picture {
+    file: String,
+    /// 按编码后的内容判断，写进 content.opf
+    media_type: &'static str,
+    bytes: Vec<u8>,
+}
+
+/// 写书时收集章节和图片。
+struct Book {
+    chapters: Vec<Chapter>,
+    pictures: Vec<Picture>,
+    /// index.toml 中 `[book.chapter_preface]`，键为章节标题
+    prefaces: Table,
+}
+
+impl Book {
+    /// 再往后第 `offset` 个章节的文件名，0 为下一章。
+    fn file_after(&self, offset: usize) -> String {
+        format!("chapter{}.xhtml", self.chapters.len() + 1 + offset)
+    }
+
+    fn chapter(&mut self, title: &str, content: &str) {
+        let mut body = format!("<h1>{}</h1>", escape(title));
+        if let Some(preface) = self.prefaces.get(title).and_then(|p| p.as_str()) {
+            write!(body, "<p class=\"preface\">{}</p>", escape(preface)).unwrap();
+        }
+        body.push_str(content);
+        self.chapters.push(Chapter {
+            file: self.file_after(0),
+            title: title.to_string(),
+            body,
+        });
+    }
+
+    /// 缩小后放进书里，返回章节中引用它的路径。
+    async fn picture(&mut self, bytes: Vec<u8>) -> Option<String> {
+        let shrunk = tokio::task::spawn_blocking(move || shrink(&bytes, MAX_SIDE))
+            .await
+            .ok()??;
+        let format = image::guess_format(&shrunk).ok()?;
+        let file = format!(
+            "images/{}.{}",
+            self.pictures.len() + 1,
+            format.extensions_str().first()?
+        );
+        self.pictures.push(Picture {
+            file: file.clone(),
+            media_type: format.to_mime_type(),
+            bytes: shrunk,
+        });
+        Some(file)
+    }
+}
+
+fn xhtml(title: &str, body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<!DOCTYPE html>\n<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" lang=\"zh-CN\" xml:lang=\"zh-CN\">\n<head>\n<title>{}</title>\n<link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/>\n</head>\n<body>\n{}\n</body>\n</html>\n",
+        escape(title),
+        body
+    )
+}
+
+const CONTAINER: &str = "<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">
+<rootfiles>
+<rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\"/>
+</rootfiles>
+</container>
+";
+
+fn package(title: &str, book: &Book) -> String {
+    let now = time::OffsetDateTime::now_utc();
+    let modified = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        now.year(),
+        now.month() as u8,
+        now.day(),
+        now.hour(),
+        now.minute(),
+        now.second()
+    );
+    let mut manifest = String::from(
+        "<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n<item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>\n",
+    );
+    let mut spine = String::new();
+    for (index, chapter) in book.chapters.iter().enumerate() {
+        writeln!(
+            manifest,
+            "<item id=\"chapter{}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>",
+            index + 1,
+            chapter.file
+        )
+        .unwrap();
+        writeln!(spine, "<itemref idref=\"chapter{}\"/>", index + 1).unwrap();
+    }
+    for (index, picture) in book.pictures.iter().enumerate() {
+        writeln!(
+            manifest,
+            "<item id=\"image{}\" href=\"{}\" media-type=\"{}\"/>",
+            index + 1,
+            picture.file,
+            picture.media_type
+        )
+        .unwrap();
+    }
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"id\" xml:lang=\"zh-CN\">
+<metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">
+<dc:identifier id=\"id\">graduate-{}</dc:identifier>
+<dc:title>{}</dc:title>
+<dc:language>zh-CN</dc:language>
+<meta property=\"dcterms:modified\">{}</meta>
+</metadata>
+<manifest>
+{}</manifest>
+<spine>
+{}</spine>
+</package>
+",
+        now.unix_timestamp(),
+        escape(title),
+        modified,
+        manifest,
+        spine
+    )
+}
+
+fn navigation(title: &str, book: &Book) -> String {
+    let mut items = String::new();
+    for chapter in &book.chapters {
+        writeln!(
+            items,
+            "<li><a href=\"{}\">{}</a></li>",
+            chapter.file,
+            escape(&chapter.title)
+        )
+        .unwrap();
+    }
+    xhtml(
+        title,
+        &format!(
+            "<nav epub:type=\"toc\" id=\"toc\">\n<h1>目录</h1>\n<ol>\n{}</ol>\n</nav>",
+            items
+        ),
+    )
+}
+
+/// 一起的时光：先写 `date`，没有的话读第一张照片的 EXIF。
+async fn together_events(
+    state: &State,
+    book: &mut Book,
+) -> Vec<(Option<Date>, String, Vec<String>)> {
+    let url_prefix = state.idxtable.get("url_prefix").unwrap().as_str().unwrap();
+    let array = state
+        .idxtable
+        .get("together_event")
+        .unwrap()
+        .as_array()
+        .unwrap();
+    let mut events = vec![];
+    for event in array {
+        let description = event
+            .get("description")
+            .and_then(|d| d.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let mut date: Option<Date> = event
+            .get("date")
+            .and_then(|date| date.as_datetime())
+            .map(|date| ShootingTime::from(date).date());
+        let mut pictures = vec![];
+        for img in event.get("image").unwrap().as_array().unwrap() {
+            let path = img.as_str().unwrap();
+            let Some(bytes) = visiting::read_or_download(&state.storage, url_prefix, path).await
+            else {
+                continue;
+            };
+            if date.is_none() {
+                date = decoding::shooting_time(&bytes).map(|shot| shot.date());
+            }
+            if let Some(file) = book.picture(bytes).await {
+                pictures.push(file);
+            }
+        }
+        events.push((date, description, pictures));
+    }
+    events.sort_by_key(|event| event.0);
+    events
+}
+
+fn figures(pictures: &[(String, String)]) -> String {
+    let mut html = String::from("<div class=\"photos\">");
+    for (caption, file) in pictures {
+        write!(
+            html,
+            "<figure><img src=\"{}\" alt=\"\"/><figcaption>{}</figcaption></figure>",
+            file,
+            escape(caption)
+        )
+        .unwrap();
+    }
+    html.push_str("</div>");
+    html
+}
+
+/// 按学期把一起的时光编成章节，再给每位同学写一章。
+pub async fn write_book(state: State, target: &Path) -> std::io::Result<()> {
+    let settings = state
+        .idxtable
+        .get("book")
+        .and_then(|book| book.as_table())
+        .cloned()
+        .unwrap_or_default();
+    let title = settings
+        .get("title")
+        .and_then(|title| title.as_str())
+        .unwrap_or("一史")
+        .to_string();
+    let mut book = Book {
+        chapters: vec![],
+        pictures: vec![],
+        prefaces: settings
+            .get("chapter_preface")
+            .and_then(|prefaces| prefaces.as_table())
+            .cloned()
+            .unwrap_or_default(),
+    };
+    if let Some(preface) = settings.get("preface").and_then(|p| p.as_str()) {
+        book.chapter("序", &format!("<p>{}</p>", escape(preface)));
+    }
+
+    let terms = &state.terms;
+    let events = together_events(&state, &mut book).await;
+    let mut chapters: Vec<(String, String)> = terms
+        .iter()
+        .map(|term| (term.name.clone(), String::new()))
+        .collect();
+    let mut other = String::new();
+    for (date, description, pictures) in &events {
+        let term = date.and_then(|date| terms::term_of(terms, *date));
+        let content = match term {
+            Some(term) => &mut chapters[term].1,
+            None => &mut other,
+        };
+        write!(content, "<h2>{}</h2>", escape(description)).unwrap();
+        if let Some(date) = date {
+            write!(content, "<p class=\"term\">{}</p>", date).unwrap();
+        }
+        let captioned: Vec<(String, String)> = pictures
+            .iter()
+            .map(|file| (String::new(), file.clone()))
+            .collect();
+        content.push_str(&figures(&captioned));
+    }
+    for (name, content) in chapters {
+        if !content.is_empty() {
+            book.chapter(&name, &content);
+        }
+    }
+    if !other.is_empty() {
+        book.chapter(OTHER_TIME, &other);
+    }
+
+    let names = names(&state);
+    let profiles = load_profiles(&state, names.len()).await;
+    let queue_table = visiting::read_events(&state).await;
+    let url_prefix = state
+        .idxtable
+        .get("url_prefix")
+        .unwrap()
+        .as_str()
+        .unwrap()
+        .to_string();
+    // 「同学们」是一份名单，每位同学的章节紧跟在它后面
+    let people: Vec<(usize, &String)> = names
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(_, name)| !name.is_empty())
+        .collect();
+    let mut roster = String::from("<ul>");
+    for (offset, (_, name)) in people.iter().enumerate() {
+        write!(
+            roster,
+            "<li><a href=\"{}\">{}</a></li>",
+            book.file_after(offset + 1),
+            escape(name)
+        )
+        .unwrap();
+    }
+    roster.push_str("</ul>");
+    book.chapter(CLASSMATES, &roster);
+    for (num, name) in people {
+        let avatar =
+            match std::fs::read(format!("{}/image/known_people/{}.jpg", state.storage, num)) {
+                Ok(bytes) => book.picture(bytes).await,
+                Err(_) => None,
+            };
+        let mut photos = vec![];
+        for photo in visiting::photos_of(&queue_table, num) {
+            if photos.len() == PHOTOS_PER_PERSON {
+                break;
+            }
+            let Some(bytes) =
+                visiting::read_or_download(&state.storage, &url_prefix, &photo.path).await
+            else {
+                continue;
+            };
+            if let Some(file) = book.picture(bytes).await {
+                photos.push((photo.description, file));
+            }
+        }
+        // 标题由 Book::chapter 写，这里只放头像和英文名
+        let mut content = String::new();
+        if avatar.is_some() || profiles[num].name_en.is_some() {
+            content.push_str("<header>");
+            if let Some(avatar) = avatar {
+                write!(content, "<img src=\"{}\" alt=\"\"/>", avatar).unwrap();
+            }
+            if let Some(name_en) = &profiles[num].name_en {
+                write!(content, "<p>{}</p>", escape(name_en)).unwrap();
+            }
+            content.push_str("</header>");
+        }
+        content.push_str(&yearbook::details(&names, &profiles[num], &photos));
+        book.chapter(name, &content);
+    }
+
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(target)?);
+    // mimetype 必须是第一个文件并且不压缩
+    let stored =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    let deflated =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    writer.start_file("mimetype", stored)?;
+    writer.write_all(b"application/epub+zip")?;
+    writer.start_file("META-INF/container.xml", deflated)?;
+    writer.write_all(CONTAINER.as_bytes())?;
+    writer.start_file("OEBPS/content.opf", deflated)?;
+    writer.write_all(package(&title, &book).as_bytes())?;
+    writer.start_file("OEBPS/nav.xhtml", deflated)?;
+    writer.write_all(navigation(&title, &book).as_bytes())?;
+    writer.start_file("OEBPS/style.css", deflated)?;
+    writer.write_all(yearbook::STYLE.as_bytes())?;
+    writer.write_all(b".preface { font-style: italic; color: #555; }\n")?;
+    for chapter in &book.chapters {
+        writer.start_file(format!("OEBPS/{}", chapter.file), deflated)?;
+        writer.write_all(xhtml(&chapter.title, &chapter.body).as_bytes())?;
+    }
+    for picture in &book.pictures {
+        writer.start_file(format!("OEBPS/{}", picture.file), stored)?;
+        writer.write_all(&picture.bytes)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/// 询问电子书的保存位置，打包成 EPUB 后写到那里。
+pub async fn export(state: State) -> String {
+    let destination = Destination::File {
+        title: "导出电子书",
+        name: String::from("一史.epub"),
+        filter: ("EPUB 电子书", &["epub"]),
+    };
+    export::save_with_notice(destination, |path| async move {
+        write_book(state, &path).await.map_err(|e| e.to_string())?;
+        Ok::<_, String>(String::new())
+    })
+    .await
+}
@@ -0,0 +1,365 @@
+use crate::export::{self, Destination};
+use crate::visiting::{self, ShootingTime};
+use crate::{decoding, site, State};
+use time::macros::offset;
+use time::{Date, UtcOffset};
+
+/// 照片上的时间都是北京时间，写进日历前换成 UTC
+const LOCAL_OFFSET: UtcOffset = offset!(+8);
+
+/// 日历中的一项，`people` 为空表示全班。
+struct Entry {
+    uid: String,
+    summary: String,
+    shot: ShootingTime,
+    people: Vec<usize>,
+}
+
+/// 先看手写的 `date`，没有或者写法不对的话读本地缓存的照片的 EXIF，不会去下载。
+fn date_of(img: &toml::Value, storage: &str, path: &str) -> Option<ShootingTime> {
+    if let Some(shot) = img
+        .get("date")
+        .and_then(|date| date.as_datetime())
+        .and_then(ShootingTime::parse)
+    {
+        return Some(shot);
+    }
+    decoding::shooting_time_of_file(&format!("{}{}", storage, path))
+}
+
+/// 「一起的时光」里的事件，另外返回没有日期而被跳过的个数；照片没缓存的也算没有日期。
+fn together_entries(idxtable: &toml::Table, storage: &str) -> (Vec<Entry>, usize) {
+    let mut entries = vec![];
+    let mut undated = 0;
+    let array = idxtable.get("together_event").unwrap().as_array().unwrap();
+    for (index, event) in array.iter().enumerate() {
+        let description = event
+            .get("description")
+            .and_then(|d| d.as_str())
+            .unwrap_or_default();
+        let mut shot = event
+            .get("date")
+            .and_then(|date| date.as_datetime())
+            .and_then(ShootingTime::parse);
+        if shot.is_none() {
+            if let Some(first) = event.get("image").unwrap().as_array().unwrap().first() {
+                let path = first.as_str().unwrap();
+                shot = decoding::shooting_time_of_file(&format!("{}{}", storage, path));
+            }
+        }
+        let Some(shot) = shot else {
+            undated += 1;
+            continue;
+        };
+        entries.push(Entry {
+            uid: format!("together-{}", index),
+            summary: description.to_string(),
+            shot,
+            people: vec![],
+        });
+    }
+    (entries, undated)
+}
+
+/// events.toml 里的事件，同一事件同一天的照片合成一项；`person` 不为空时只留下有 ta 的。
+/// 另外返回没有拍摄日期而被跳过的照片数，没缓存又没写 `date` 的照片也算在里面。
+fn photo_entries(
+    storage: &str,
+    queue_table: &toml::Table,
+    person: Option<usize>,
+) -> (Vec<Entry>, usize) {
+    let mut entries: Vec<Entry> = vec![];
+    let mut undated = 0;
+    for kind in ["experience", "event"] {
+        for (index, event) in queue_table
+            .get(kind)
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .iter()
+            .enumerate()
+        {
+            let description = event.get("description").unwrap().as_str().unwrap();
+            for img in event.get("image").unwrap().as_array().unwrap() {
+                let with: Vec<usize> = img
+                    .get("with")
+                    .and_then(|with| with.as_array())
+                    .map(|with| {
+                        with.iter()
+                            .map(|num| num.as_integer().unwrap() as usize)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                if let Some(person) = person {
+                    if !with.is_empty() && !with.contains(&person) {
+                        continue;
+                    }
+                }
+                let path = img.get("path").unwrap().as_str().unwrap();
+                let path = if kind == "experience" {
+                    format!("image/experience/{}", path)
+                } else {
+                    path.to_string()
+                };
+                let Some(shot) = date_of(img, storage, &path) else {
+                    undated += 1;
+                    continue;
+                };
+                let uid = format!("{}-{}-{}", kind, index, compact_date(shot.date()));
+                match entries.iter_mut().find(|entry| entry.uid == uid) {
+                    Some(entry) => {
+                        // 空的 `with` 是全班合照，合并后也算全班
+                        if entry.people.is_empty() || with.is_empty() {
+                            entry.people.clear();
+                        } else {
+                            for num in with {
+                                if !entry.people.contains(&num) {
+                                    entry.people.push(num);
+                                }
+                            }
+                        }
+                    }
+                    None => entries.push(Entry {
+                        uid,
+                        summary: description.to_string(),
+                        shot,
+                        people: with,
+                    }),
+                }
+            }
+        }
+    }
+    (entries, undated)
+}
+
+fn compact_date(date: Date) -> String {
+    format!(
+        "{:04}{:02}{:02}",
+        date.year(),
+        date.month() as u8,
+        date.day()
+    )
+}
+
+/// 按 RFC 5545 转义逗号、分号、反斜杠和换行。
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// 每行不超过 75 个字节，续行以空格开头，不能把一个汉字拆开。
+fn fold(ics: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            ics.push_str("\r\n ");
+            width = 1;
+        }
+        ics.push(c);
+        width += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+/// UTC 时间，形如 `20230901T003000Z`。
+fn utc_stamp(moment: time::OffsetDateTime) -> String {
+    let moment = moment.to_offset(UtcOffset::UTC);
+    format!(
+        "{}T{:02}{:02}{:02}Z",
+        compact_date(moment.date()),
+        moment.hour(),
+        moment.minute(),
+        moment.second()
+    )
+}
+
+fn calendar(name: &str, entries: &[Entry], names: &[String]) -> String {
+    let stamp = utc_stamp(time::OffsetDateTime::now_utc());
+    let mut ics = String::new();
+    for line in [
+        "BEGIN:VCALENDAR",
+        "VERSION:2.0",
+        "PRODID:-//Class1//Graduate//ZH",
+        "CALSCALE:GREGORIAN",
+        format!("X-WR-CALNAME:{}", escape(name)).as_str(),
+    ] {
+        fold(&mut ics, line);
+    }
+    for entry in entries {
+        let people = if entry.people.is_empty() {
+            String::from("全班")
+        } else {
+            entry
+                .people
+                .iter()
+                .filter_map(|&num| names.get(num))
+                .cloned()
+                .collect::<Vec<_>>()
+                .join("、")
+        };
+        let mut lines = vec![
+            String::from("BEGIN:VEVENT"),
+            format!("UID:{}@graduate", entry.uid),
+            format!("DTSTAMP:{}", stamp),
+            format!("SUMMARY:{}", escape(&entry.summary)),
+            format!(
+                "DESCRIPTION:{}",
+                escape(&format!("拍摄于 {}\n在场：{}", entry.shot, people))
+            ),
+        ];
+        match &entry.shot {
+            ShootingTime::Approximate(date) => {
+                lines.push(format!("DTSTART;VALUE=DATE:{}", compact_date(*date)));
+                lines.push(format!(
+                    "DTEND;VALUE=DATE:{}",
+                    compact_date(date.next_day().unwrap())
+                ));
+            }
+            ShootingTime::Precise(precise) => {
+                lines.push(format!(
+                    "DTSTART:{}",
+                    utc_stamp(precise.assume_offset(LOCAL_OFFSET))
+                ));
+                lines.push(String::from("DURATION:PT1H"));
+            }
+        }
+        // 每年的这一天提醒一次；全天的事件在早上九点提醒
+        let trigger = match entry.shot {
+            ShootingTime::Approximate(_) => "PT9H",
+            ShootingTime::Precise(_) => "PT0S",
+        };
+        lines.extend([
+            String::from("RRULE:FREQ=YEARLY"),
+            String::from("BEGIN:VALARM"),
+            String::from("ACTION:DISPLAY"),
+            format!(
+                "DESCRIPTION:{}",
+                escape(&format!("那年今日：{}", entry.summary))
+            ),
+            format!("TRIGGER;RELATED=START:{}", trigger),
+            String::from("END:VALARM"),
+            String::from("END:VEVENT"),
+        ]);
+        for line in lines {
+            fold(&mut ics, &line);
+        }
+    }
+    fold(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+/// 询问保存位置，导出 .ics 日历；`person` 不为空时只导出 ta 的时间线，没有日期的照片跳过并计数。
+pub async fn export(state: State, person: Option<usize>) -> String {
+    let names = site::names(&state);
+    let name = match person {
+        Some(num) => format!(
+            "{}的时间线",
+            names.get(num).map(String::as_str).unwrap_or_default()
+        ),
+        None => String::from("一起的时光"),
+    };
+    let destination = Destination::File {
+        title: "导出日历",
+        name: format!("{}.ics", name),
+        filter: ("日历", &["ics"]),
+    };
+    export::save_with_notice(destination, |path| async move {
+        let queue_table = visiting::read_events(&state).await;
+        // 读 EXIF 要打开文件，放到别的线程
+        let ((mut entries, together_undated), (photos, photo_undated)) =
+            tokio::task::spawn_blocking(move || {
+                (
+                    together_entries(&state.idxtable, &state.storage),
+                    photo_entries(&state.storage, &queue_table, person),
+                )
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+        entries.extend(photos);
+        entries.sort_by_key(|entry| entry.shot.date());
+        std::fs::write(&path, calendar(&name, &entries, &names)).map_err(|e| e.to_string())?;
+        let mut detail = format!("，共 {} 个事件", entries.len());
+        if together_undated + photo_undated > 0 {
+            detail.push_str(&format!(
+                "，另有 {} 个事件和 {} 张照片没有拍摄日期，没写进日历",
+                together_undated, photo_undated
+            ));
+        }
+        Ok::<_, String>(detail)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn escape_special_characters() {
+        assert_eq!(escape("a,b;c\\d\ne"), "a\\,b\\;c\\\\d\\ne");
+        assert_eq!(escape("春游"), "春游");
+    }
+
+    #[test]
+    fn fold_long_lines() {
+        let mut ics = String::new();
+        fold(&mut ics, "SUMMARY:short");
+        assert_eq!(ics, "SUMMARY:short\r\n");
+
+        let mut ics = String::new();
+        let line = format!("SUMMARY:{}", "春".repeat(40));
+        fold(&mut ics, &line);
+        let lines: Vec<&str> = ics.trim_end_matches("\r\n").split("\r\n").collect();
+        assert!(lines.len() > 1);
+        for line in &lines {
+            assert!(line.len() <= 75);
+        }
+        assert!(lines[1..].iter().all(|line| line.starts_with(' ')));
+        let unfolded: String = lines
+            .iter()
+            .enumerate()
+            .map(|(index, line)| if index == 0 { *line } else { &line[1..] })
+            .collect();
+        assert_eq!(unfolded, line);
+    }
+
+    #[test]
+    fn precise_times_in_utc() {
+        let local = datetime!(2023-09-01 08:30:00);
+        assert_eq!(
+            utc_stamp(local.assume_offset(LOCAL_OFFSET)),
+            "20230901T003000Z"
+        );
+        let early = datetime!(2023-09-01 07:00:00);
+        assert_eq!(
+            utc_stamp(early.assume_offset(LOCAL_OFFSET)),
+            "20230831T230000Z"
+        );
+    }
+
+    #[test]
+    fn uncached_photos_without_date_are_undated() {
+        let queue_table: toml::Table = r#"
+            event = []
+            [[experience]]
+            description = "春游"
+            image = [
+                { path = "1.jpg", date = 2021-04-01, with = [1] },
+                { path = "2.jpg", date = 2021-04-01, with = [2] },
+                { path = "3.jpg", with = [1] },
+            ]
+            "#
+        .parse()
+        .unwrap();
+        let (entries, undated) = photo_entries("/nonexistent/", &queue_table, None);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].people, [1, 2]);
+        assert_eq!(undated, 1);
+        let (entries, undated) = photo_entries("/nonexistent/", &queue_table, Some(2));
+        assert_eq!(entries[0].people, [2]);
+        assert_eq!(undated, 0);
+    }
+}
@@ -0,0 +1,173 @@
+use crate::drawing::{draw_centered, fit, text_width};
+use crate::export::{self, Destination};
+use crate::terms::{self, Term};
+use crate::{visiting, ChoosingState, VisitingState};
+use ab_glyph::FontRef;
+use image::{imageops, Rgba, RgbaImage};
+use std::path::PathBuf;
+
+const WIDTH: u32 = 1080;
+const HEIGHT: u32 = 1440;
+const MARGIN: u32 = 60;
+const FOOTER: &str = "有你，才是一班。";
+
+const BACKGROUND: Rgba<u8> = Rgba([250, 246, 238, 255]);
+const PLACEHOLDER: Rgba<u8> = Rgba([220, 228, 240, 255]);
+const NAME_COLOR: Rgba<u8> = Rgba([34, 34, 34, 255]);
+const GREY: Rgba<u8> = Rgba([120, 120, 120, 255]);
+const QUOTE_COLOR: Rgba<u8> = Rgba([240, 134, 80, 255]);
+const BLUE: Rgba<u8> = Rgba([120, 158, 204, 255]);
+
+/// 一张分享卡片上的内容。
+#[derive(Clone, Debug)]
+pub struct Card {
+    /// 照片或头像，相对于存储目录
+    pub picture: String,
+    pub name: String,
+    pub nickname: Option<String>,
+    pub quote: Option<String>,
+    pub description: Option<String>,
+    pub date: Option<String>,
+}
+
+/// 资料页上的卡片：头像、昵称和第一条评价。
+pub fn of_profile(choosing: &ChoosingState, num: usize) -> Card {
+    let profile = &choosing.profiles[num];
+    Card {
+        picture: format!("/image/known_people/{}.jpg", num),
+        name: choosing.avatars[num].name.clone(),
+        nickname: (!profile.nickname.is_empty()).then(|| profile.nickname.join("、")),
+        quote: profile
+            .comment
+            .first()
+            .map(|comment| format!("“{}”", comment.description)),
+        description: None,
+        date: None,
+    }
+}
+
+/// 时间线上当前这张照片的卡片。
+pub fn of_photo(terms: &[Term], displayer: &VisitingState) -> Card {
+    let events = displayer.events.lock().unwrap();
+    let event = &events[displayer.on_event];
+    let experience = &event.experiences[event.on_experience];
+    Card {
+        picture: experience.path.clone(),
+        name: displayer.character_name.clone(),
+        nickname: None,
+        quote: None,
+        description: Some(event.description.clone()),
+        date: experience
+            .shot
+            .as_ref()
+            .map(|shot| terms::caption(terms, shot)),
+    }
+}
+
+/// 按宽度逐字换行，最多 `max_lines` 行，多出的部分用省略号表示。
+fn wrap(font: &FontRef, size: f32, content: &str, max_width: f32, max_lines: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    for c in content.chars() {
+        if c == '\n' {
+            lines.push(std::mem::take(&mut line));
+            continue;
+        }
+        line.push(c);
+        if text_width(font, size, &line) > max_width {
+            line.pop();
+            lines.push(std::mem::replace(&mut line, c.to_string()));
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    if lines.len() > max_lines {
+        let rest = lines.split_off(max_lines - 1).concat();
+        lines.push(fit(font, size, &format!("{}…", rest), max_width));
+    }
+    lines
+}
+
+fn render(font: &FontRef, picture: Option<&[u8]>, card: &Card) -> RgbaImage {
+    let mut canvas = RgbaImage::from_pixel(WIDTH, HEIGHT, BACKGROUND);
+    let photo_width = WIDTH - MARGIN * 2;
+    let photo_height = photo_width * 3 / 4;
+    let photo = picture
+        .and_then(|bytes| image::load_from_memory(bytes).ok())
+        .map(|photo| {
+            photo
+                .resize_to_fill(photo_width, photo_height, imageops::FilterType::Triangle)
+                .to_rgba8()
+        })
+        .unwrap_or_else(|| RgbaImage::from_pixel(photo_width, photo_height, PLACEHOLDER));
+    imageops::overlay(&mut canvas, &photo, MARGIN as i64, MARGIN as i64);
+
+    let mut top = (MARGIN * 2 + photo_height) as f32;
+    draw_centered(&mut canvas, font, 72.0, &card.name, top, NAME_COLOR);
+    top += 100.0;
+    if let Some(nickname) = &card.nickname {
+        draw_centered(&mut canvas, font, 34.0, nickname, top, GREY);
+        top += 60.0;
+    }
+    if let Some(quote) = &card.quote {
+        for line in wrap(font, 38.0, quote, photo_width as f32, 3) {
+            draw_centered(&mut canvas, font, 38.0, &line, top, QUOTE_COLOR);
+            top += 52.0;
+        }
+    }
+    if let Some(description) = &card.description {
+        for line in wrap(font, 44.0, description, photo_width as f32, 2) {
+            draw_centered(&mut canvas, font, 44.0, &line, top, NAME_COLOR);
+            top += 60.0;
+        }
+    }
+    if let Some(date) = &card.date {
+        draw_centered(&mut canvas, font, 30.0, date, top, BLUE);
+    }
+    draw_centered(
+        &mut canvas,
+        font,
+        40.0,
+        FOOTER,
+        (HEIGHT - MARGIN * 2) as f32,
+        BLUE,
+    );
+    canvas
+}
+
+/// 图片文件夹里一个还没被占用的文件名。
+fn target(name: &str) -> Option<PathBuf> {
+    let dirs = directories::UserDirs::new()?;
+    let folder = dirs
+        .picture_dir()
+        .unwrap_or_else(|| dirs.home_dir())
+        .to_path_buf();
+    let mut path = folder.join(format!("{}的卡片.png", name));
+    let mut count = 2;
+    while path.exists() {
+        path = folder.join(format!("{}的卡片 ({}).png", name, count));
+        count += 1;
+    }
+    Some(path)
+}
+
+/// 把卡片画成 png，不用询问，直接存进图片文件夹里一个没被占用的文件名。
+pub async fn make(storage: String, url_prefix: String, card: Card) -> String {
+    let Some(path) = target(&card.name) else {
+        return String::from("找不到图片文件夹");
+    };
+    export::save_with_notice(Destination::Fixed(path), |path| async move {
+        let picture = visiting::read_or_download(&storage, &url_prefix, &card.picture).await;
+        tokio::task::spawn_blocking(move || -> Result<(), String> {
+            let font = FontRef::try_from_slice(crate::FONT).map_err(|e| e.to_string())?;
+            render(&font, picture.as_deref(), &card)
+                .save_with_format(&path, image::ImageFormat::Png)
+                .map_err(|e| e.to_string())
+        })
+        .await
+        .unwrap()?;
+        Ok::<_, String>(String::new())
+    })
+    .await
+}
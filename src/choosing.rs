@@ -1,35 +1,274 @@
-use crate::{ChoosingState, State};
+use crate::terms::{self, Term};
+use crate::{decoding, validation, visiting, ChoosingState, State};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
 use iced::widget::image;
+use pinyin::ToPinyin;
 use rand::Rng;
 use reqwest::Client;
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use std::fs;
 use std::io::Write;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
-use toml::value::{Array, Table};
-
-pub const CHARACTERS_WITH_NO_PHOTOS: [usize; 15] =
-    [38, 39, 41, 42, 45, 46, 47, 49, 50, 51, 52, 53, 54, 58, 59];
-pub const SEMESTER_NAMES: [&str; 5] = [
-    "七年级上学期",
-    "七年级下学期",
-    "八年级上学期",
-    "八年级下学期",
-    "九年级上学期",
-];
+use toml::value::{Datetime, Table};
+
+/// 资料中的 `role`，缺省为学生。
+#[derive(Clone, Copy, Default, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    #[default]
+    Student,
+    HeadTeacher,
+    SubjectTeacher,
+}
+
+impl Role {
+    pub const ALL: [Role; 3] = [Role::Student, Role::HeadTeacher, Role::SubjectTeacher];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Role::Student => "同学",
+            Role::HeadTeacher => "班主任",
+            Role::SubjectTeacher => "任课老师",
+        }
+    }
+
+    pub fn is_teacher(&self) -> bool {
+        *self != Role::Student
+    }
+}
+
+/// 老师在哪些学期教了哪门课。
+#[derive(Clone, Deserialize, Debug)]
+pub struct Teaching {
+    pub subject: String,
+    #[serde(default)]
+    pub terms: Vec<String>,
+}
 
 #[derive(Clone, Default, Deserialize, Debug)]
+pub struct Anecdote {
+    pub interests: Option<String>,
+    pub want: Option<String>,
+    pub embarrassment: Option<String>,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Article {
+    pub content: String,
+    pub date: Option<Datetime>,
+    pub link: String,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Comment {
+    pub from: usize,
+    pub description: String,
+    pub date: Option<Datetime>,
+}
+
+/// 某个学期的班主任评语。
+#[derive(Clone, Debug)]
+pub struct Review {
+    pub term: String,
+    pub content: String,
+}
+
+/// 被描述的人是 `by` 的 `is`。
+#[derive(Clone, Deserialize, Debug)]
+pub struct Relationship {
+    pub by: usize,
+    pub is: String,
+}
+
+/// 一位同学或老师的资料，各部分都在读取时校验过，缺了的就是空的。
+#[derive(Clone, Default, Debug)]
 pub struct Profile {
-    pub anecdote: Option<Table>,
-    pub article: Option<Array>,
-    pub comment: Option<Array>,
+    pub role: Role,
+    /// 只有老师才有
+    pub teaching: Vec<Teaching>,
+    pub anecdote: Option<Anecdote>,
+    pub article: Vec<Article>,
+    pub comment: Vec<Comment>,
     pub introduction: Option<String>,
     pub name_en: Option<String>,
-    pub nickname: Option<Array>,
-    pub plots: Option<Array>,
-    pub relationship: Option<Array>,
-    pub reviews: Option<Array>,
+    pub nickname: Vec<String>,
+    pub plots: Vec<String>,
+    pub relationship: Vec<Relationship>,
+    /// 按学期先后排列
+    pub reviews: Vec<Review>,
+    /// 读取时发现的问题，交给 [`crate::validation`] 给内容作者看
+    pub warnings: Vec<String>,
+}
+
+/// 读取资料中的一个数组，格式不对的条目跳过并提示内容作者。
+fn section<T: DeserializeOwned>(
+    num: usize,
+    table: &Table,
+    key: &str,
+    warnings: &mut Vec<String>,
+) -> Vec<T> {
+    let Some(value) = table.get(key) else {
+        return vec![];
+    };
+    let Some(array) = value.as_array() else {
+        warnings.push(format!("profile/{}.toml: `{}` 应该是数组", num, key));
+        return vec![];
+    };
+    let mut items = vec![];
+    for item in array {
+        match item.clone().try_into() {
+            Ok(item) => items.push(item),
+            Err(err) => warnings.push(format!(
+                "profile/{}.toml: 跳过格式不对的 `{}`：{}",
+                num, key, err
+            )),
+        }
+    }
+    items
+}
+
+fn single<T: DeserializeOwned>(
+    num: usize,
+    table: &Table,
+    key: &str,
+    warnings: &mut Vec<String>,
+) -> Option<T> {
+    match table.get(key)?.clone().try_into() {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warnings.push(format!(
+                "profile/{}.toml: 跳过格式不对的 `{}`：{}",
+                num, key, err
+            ));
+            None
+        }
+    }
+}
+
+/// `reviews` 以学期名为键；旧的资料按学期顺序写成数组，也照样读取，
+/// 学期表里没有的部分记作「第 N 学期」。
+fn reviews(num: usize, table: &Table, terms: &[Term], warnings: &mut Vec<String>) -> Vec<Review> {
+    let mut review = |term: &str, content: &toml::Value| {
+        let Some(content) = content.as_str() else {
+            warnings.push(format!("profile/{}.toml: {} 的评语应该是字符串", num, term));
+            return None;
+        };
+        Some(Review {
+            term: term.to_string(),
+            content: content.to_string(),
+        })
+    };
+    match table.get("reviews") {
+        None => vec![],
+        Some(toml::Value::Table(keyed)) => {
+            let mut unknown = vec![];
+            for key in keyed.keys() {
+                if !terms.iter().any(|term| &term.name == key) {
+                    unknown.push(format!(
+                        "profile/{}.toml: `reviews` 中的学期「{}」不存在",
+                        num, key
+                    ));
+                }
+            }
+            let reviews = terms
+                .iter()
+                .filter_map(|term| review(&term.name, keyed.get(&term.name)?))
+                .collect();
+            warnings.extend(unknown);
+            reviews
+        }
+        Some(toml::Value::Array(ordered)) => {
+            let too_many = !terms.is_empty() && ordered.len() > terms.len();
+            let reviews = ordered
+                .iter()
+                .enumerate()
+                .filter_map(|(index, content)| match terms.get(index) {
+                    Some(term) => review(&term.name, content),
+                    None => review(&format!("第 {} 学期", index + 1), content),
+                })
+                .collect();
+            if too_many {
+                warnings.push(format!("profile/{}.toml: `reviews` 比学期数还多", num));
+            }
+            reviews
+        }
+        Some(_) => {
+            warnings.push(format!("profile/{}.toml: `reviews` 应该是表或数组", num));
+            vec![]
+        }
+    }
+}
+
+/// 编号为 `who` 的人是否存在，`count` 为同学人数加一。
+fn known(num: usize, count: usize, who: usize, key: &str, warnings: &mut Vec<String>) -> bool {
+    let valid = who >= 1 && who < count;
+    if !valid {
+        warnings.push(format!(
+            "profile/{}.toml: `{}` 中的编号 {} 不存在",
+            num, key, who
+        ));
+    }
+    valid
+}
+
+impl Profile {
+    /// 解析第 `num` 位同学的资料，`count` 为同学人数加一，用来检查编号。
+    /// 只有整个文件都不是合法的 toml 时才返回 `None`，其余问题记在 `warnings` 里。
+    pub fn parse(num: usize, text: &str, count: usize, terms: &[Term]) -> Option<Profile> {
+        let table: Table = toml::from_str(text).ok()?;
+        let mut warnings = vec![];
+        let mut relationship: Vec<Relationship> =
+            section(num, &table, "relationship", &mut warnings);
+        relationship
+            .retain(|relation| known(num, count, relation.by, "relationship", &mut warnings));
+        let mut comment: Vec<Comment> = section(num, &table, "comment", &mut warnings);
+        comment.retain(|comment| known(num, count, comment.from, "comment", &mut warnings));
+        let role: Role = single(num, &table, "role", &mut warnings).unwrap_or_default();
+        let teaching: Vec<Teaching> = section(num, &table, "teaching", &mut warnings);
+        if !role.is_teacher() && !teaching.is_empty() {
+            warnings.push(format!("profile/{}.toml: 只有老师才需要写 `teaching`", num));
+        }
+        for term in teaching.iter().flat_map(|teaching| &teaching.terms) {
+            if !terms.iter().any(|known| &known.name == term) {
+                warnings.push(format!(
+                    "profile/{}.toml: `teaching` 中的学期「{}」不存在",
+                    num, term
+                ));
+            }
+        }
+        Some(Profile {
+            role,
+            teaching,
+            anecdote: single(num, &table, "anecdote", &mut warnings),
+            article: section(num, &table, "article", &mut warnings),
+            comment,
+            introduction: single(num, &table, "introduction", &mut warnings),
+            name_en: single(num, &table, "name_en", &mut warnings),
+            nickname: section(num, &table, "nickname", &mut warnings),
+            plots: section(num, &table, "plots", &mut warnings),
+            relationship,
+            reviews: reviews(num, &table, terms, &mut warnings),
+            warnings,
+        })
+    }
+}
+
+/// 这个学期的班主任；`teaching` 中没写学期的班主任算作每个学期都是。
+pub fn head_teacher(profiles: &[Profile], term: &str) -> Option<usize> {
+    profiles.iter().position(|profile| {
+        profile.role == Role::HeadTeacher
+            && (profile
+                .teaching
+                .iter()
+                .all(|teaching| teaching.terms.is_empty())
+                || profile
+                    .teaching
+                    .iter()
+                    .any(|teaching| teaching.terms.iter().any(|taught| taught == term)))
+    })
 }
 
 #[derive(Clone, Debug)]
@@ -38,12 +277,83 @@ pub struct Avatar {
     pub photo: image::Handle,
     pub emoji: Vec<Emoji>,
     pub shown: bool,
+    /// 搜索时可以匹配的写法，见 [`search_keys`]
+    pub search_keys: Vec<String>,
+}
+
+fn push_with_pinyin(keys: &mut Vec<String>, word: &str) {
+    let mut full = String::new();
+    let mut initials = String::new();
+    for syllable in word.to_pinyin().flatten() {
+        full.push_str(syllable.plain());
+        initials.push_str(syllable.first_letter());
+    }
+    keys.push(word.to_lowercase());
+    if !full.is_empty() {
+        keys.push(full);
+        keys.push(initials);
+    }
+}
+
+/// 姓名、姓名的全拼与拼音首字母（输入 "zs" 就能找到张三）、英文名以及各个昵称。
+pub fn search_keys(name: &str, profile: &Profile) -> Vec<String> {
+    let mut keys = vec![];
+    push_with_pinyin(&mut keys, name);
+    if let Some(name_en) = &profile.name_en {
+        keys.push(name_en.to_lowercase());
+    }
+    for nickname in &profile.nickname {
+        push_with_pinyin(&mut keys, nickname);
+    }
+    keys
+}
+
+/// 与输入最接近的写法的得分，一个都对不上时返回 `None`。
+pub fn match_score(matcher: &SkimMatcherV2, keys: &[String], description: &str) -> Option<i64> {
+    let description = description.trim().to_lowercase();
+    keys.iter()
+        .filter_map(|key| matcher.fuzzy_match(key, &description))
+        .max()
+}
+
+/// 按得分从高到低排列匹配上的角色，没有输入时按学号排列。
+pub fn rank_avatars(avatars: &mut [Avatar], description: &str) -> Vec<usize> {
+    if description.trim().is_empty() {
+        for avatar in avatars.iter_mut().skip(1) {
+            avatar.shown = true;
+        }
+        return (1..avatars.len()).collect();
+    }
+    let matcher = SkimMatcherV2::default().ignore_case();
+    let mut scored = vec![];
+    for (index, avatar) in avatars.iter_mut().enumerate().skip(1) {
+        let score = match_score(&matcher, &avatar.search_keys, description);
+        avatar.shown = score.is_some();
+        if let Some(score) = score {
+            scored.push((score, index));
+        }
+    }
+    // 得分相同的按学号排
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    scored.into_iter().map(|(_, index)| index).collect()
 }
 
 #[derive(Clone, Debug)]
 pub struct Emoji {
     pub emoji: image::Handle,
     pub emoji_name: String,
+    /// 本地文件的路径
+    pub path: String,
+    pub animation: Option<decoding::Animation>,
+}
+
+impl Emoji {
+    pub fn current_frame(&self) -> image::Handle {
+        match &self.animation {
+            Some(animation) => animation.current_frame(),
+            None => self.emoji.clone(),
+        }
+    }
 }
 
 pub async fn get_configs(
@@ -91,8 +401,11 @@ pub async fn get_configs(
     fs::create_dir_all(Path::new(&format!("{}/profile", state.storage))).unwrap();
     fs::create_dir_all(Path::new(&format!("{}/image/known_people", state.storage))).unwrap();
 
+    let count = names.len();
+    let terms = Arc::new(state.terms.clone());
     for num in 1..names.len() {
         let img_mutex = img_mutex.clone();
+        let terms = terms.clone();
         let profile_mutex = profile_mutex.clone();
         let storage = state.storage.clone();
         let url_prefix = url_prefix.clone();
@@ -124,7 +437,8 @@ pub async fn get_configs(
             }
             if profile_path.is_file() {
                 let profile_text = fs::read_to_string(&profile_path).unwrap();
-                if let Ok(res) = toml::from_str(profile_text.as_str()) {
+                if let Some(res) = Profile::parse(num, &profile_text, count, &terms) {
+                    validation::report(&res.warnings);
                     let mut profile_array = profile_mutex.lock().unwrap();
                     profile_array[num] = res;
                     return;
@@ -140,8 +454,10 @@ pub async fn get_configs(
                 .unwrap();
             let mut profile_file = std::fs::File::create(&profile_path).unwrap();
             profile_file.write_all(&profile_text.as_bytes()).unwrap();
+            let profile = Profile::parse(num, &profile_text, count, &terms).unwrap_or_default();
+            validation::report(&profile.warnings);
             let mut profile_array = profile_mutex.lock().unwrap();
-            profile_array[num] = toml::from_str(profile_text.as_str()).unwrap();
+            profile_array[num] = profile;
         });
         threads.push(t);
     }
@@ -160,10 +476,20 @@ pub async fn get_configs(
             let num = num_string.parse::<usize>().unwrap();
             let emoji_path = Path::new(&emoji_dir);
             if emoji_path.is_file() {
+                let animation = if decoding::may_be_animated(&emoji_dir) {
+                    let emoji_bytes = fs::read(&emoji_path).unwrap();
+                    tokio::task::spawn_blocking(move || decoding::decode_animation(&emoji_bytes))
+                        .await
+                        .unwrap()
+                } else {
+                    None
+                };
                 let mut emoji_array = emoji_mutex.lock().unwrap();
                 emoji_array[num].push(Emoji {
                     emoji_name,
+                    path: emoji_dir.clone(),
                     emoji: image::Handle::from_path(&emoji_path),
+                    animation,
                 });
             } else {
                 let emoji_bytes = reqwest::get(&emoji_url)
@@ -174,10 +500,20 @@ pub async fn get_configs(
                     .unwrap();
                 let mut emoji_file = std::fs::File::create(&emoji_path).unwrap();
                 emoji_file.write_all(&emoji_bytes).unwrap();
+                let animation = if decoding::may_be_animated(&emoji_dir) {
+                    let emoji_bytes = emoji_bytes.clone();
+                    tokio::task::spawn_blocking(move || decoding::decode_animation(&emoji_bytes))
+                        .await
+                        .unwrap()
+                } else {
+                    None
+                };
                 let mut emoji_array = emoji_mutex.lock().unwrap();
                 emoji_array[num].push(Emoji {
                     emoji_name,
+                    path: emoji_dir.clone(),
                     emoji: image::Handle::from_memory(emoji_bytes),
+                    animation,
                 });
             }
         });
@@ -188,6 +524,16 @@ pub async fn get_configs(
     for t in threads {
         t.await?;
     }
+    // index.toml 没有标出来的话，events.toml 里一张都没有 ta 的就是没有照片
+    let no_photos = match terms::flagged(&state.idxtable, "no_photos") {
+        Some(no_photos) => no_photos,
+        None => {
+            let queue_table = visiting::read_events(&state).await;
+            (1..count)
+                .filter(|&num| visiting::photos_of(&queue_table, num).is_empty())
+                .collect()
+        }
+    };
     let img_fetched = img_mutex.lock().unwrap().to_vec();
     let profile_fetched = profile_mutex.lock().unwrap();
     let emoji_fetched = emoji_mutex.lock().unwrap();
@@ -197,6 +543,7 @@ pub async fn get_configs(
         photo: image::Handle::from_memory(vec![]),
         emoji: Vec::new(),
         shown: false,
+        search_keys: Vec::new(),
     });
     for (index, value) in img_fetched.iter().enumerate() {
         if let Some(img) = &value {
@@ -205,11 +552,21 @@ pub async fn get_configs(
                 photo: img.to_owned(),
                 emoji: emoji_fetched[index].to_owned(),
                 shown: true,
+                search_keys: search_keys(&names[index], &profile_fetched[index]),
             });
         }
     }
+    let ranking = (1..avatars.len()).collect();
     let mut rng = rand::thread_rng();
     let element_count: usize = rng.gen_range(6..=8);
+    // 从共同回忆返回时，之前选中的同学还在
+    let (companions, require_all) = match &state.stage {
+        crate::Stage::ShowingPlots(displayer) if displayer.company.people.len() > 1 => (
+            displayer.company.people.clone(),
+            displayer.company.require_all,
+        ),
+        _ => (vec![], true),
+    };
     let previous_stage = if let crate::Stage::EntryEvents(previous) = state.stage {
         Some(previous)
     } else {
@@ -219,8 +576,18 @@ pub async fn get_configs(
         stage: crate::Stage::ChoosingCharacter(ChoosingState {
             avatars,
             element_count,
+            ranking,
+            network: None,
+            seating: None,
+            role: None,
+            comparison: None,
+            emojis: None,
+            notice: String::new(),
+            companions,
+            require_all,
             on_character,
             profiles: profile_fetched.to_vec(),
+            no_photos,
             description: String::from(""),
             previous_stage,
             homepage_offset,
@@ -232,3 +599,127 @@ pub async fn get_configs(
 pub fn generate_scrollable_id(i: usize) -> iced::widget::scrollable::Id {
     iced::widget::scrollable::Id::new(format!("ChoosingCharacter-{}", i))
 }
+
+/// 测试用的头像，照片是一个像素。
+#[cfg(test)]
+pub fn avatar(name: &str, profile: &Profile) -> Avatar {
+    Avatar {
+        name: name.to_string(),
+        photo: image::Handle::from_pixels(1, 1, vec![0; 4]),
+        emoji: vec![],
+        shown: true,
+        search_keys: search_keys(name, profile),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    fn terms() -> Vec<Term> {
+        vec![
+            Term {
+                name: String::from("七年级上学期"),
+                start: date!(2020 - 09 - 01),
+                end: date!(2021 - 01 - 31),
+            },
+            Term {
+                name: String::from("七年级下学期"),
+                start: date!(2021 - 02 - 01),
+                end: date!(2021 - 08 - 31),
+            },
+        ]
+    }
+
+    #[test]
+    fn search_keys_include_pinyin_and_nicknames() {
+        let profile = Profile {
+            name_en: Some(String::from("Alice")),
+            nickname: vec![String::from("小李")],
+            ..Default::default()
+        };
+        let keys = search_keys("张三", &profile);
+        for key in ["张三", "zhangsan", "zs", "alice", "小李", "xiaoli", "xl"] {
+            assert!(keys.contains(&key.to_string()), "缺少 {}", key);
+        }
+    }
+
+    #[test]
+    fn rank_avatars_orders_by_score_and_hides_misses() {
+        let profile = Profile::default();
+        let mut avatars = vec![
+            avatar("", &profile),
+            avatar("李四", &profile),
+            avatar("张三", &profile),
+            avatar("张三丰", &profile),
+        ];
+        let ranking = rank_avatars(&mut avatars, "zs");
+        assert_eq!(ranking[..2], [2, 3]);
+        assert!(!ranking.contains(&1));
+        assert!(!avatars[1].shown);
+        assert_eq!(rank_avatars(&mut avatars, " "), vec![1, 2, 3]);
+        assert!(avatars[1].shown);
+    }
+
+    #[test]
+    fn parse_collects_warnings_instead_of_failing() {
+        let text = r#"
+            nickname = "不是数组"
+            relationship = [{ by = 2, is = "同桌" }, { by = 99, is = "同桌" }]
+            anecdote = "不是表"
+        "#;
+        let profile = Profile::parse(1, text, 5, &[]).unwrap();
+        assert!(profile.nickname.is_empty());
+        assert_eq!(profile.relationship.len(), 1);
+        assert_eq!(profile.relationship[0].by, 2);
+        assert!(profile.anecdote.is_none());
+        assert_eq!(profile.warnings.len(), 3);
+        assert!(Profile::parse(1, "不是 toml", 5, &[]).is_none());
+    }
+
+    #[test]
+    fn parse_reads_ordered_reviews_by_term() {
+        let text = r#"reviews = ["第一学期", "第二学期", "多出来的"]"#;
+        let profile = Profile::parse(1, text, 5, &terms()).unwrap();
+        assert_eq!(profile.reviews.len(), 3);
+        assert_eq!(profile.reviews[1].term, "七年级下学期");
+        assert_eq!(profile.reviews[2].term, "第 3 学期");
+        assert_eq!(profile.warnings.len(), 1);
+    }
+
+    #[test]
+    fn parse_reads_keyed_reviews_in_term_order() {
+        let text = r#"
+            [reviews]
+            "七年级下学期" = "下"
+            "七年级上学期" = "上"
+            "不存在的学期" = "？"
+        "#;
+        let profile = Profile::parse(1, text, 5, &terms()).unwrap();
+        let contents: Vec<&str> = profile
+            .reviews
+            .iter()
+            .map(|review| review.content.as_str())
+            .collect();
+        assert_eq!(contents, ["上", "下"]);
+        assert_eq!(profile.warnings.len(), 1);
+    }
+
+    #[test]
+    fn parse_checks_teaching_against_role_and_terms() {
+        let text = r#"
+            role = "subject_teacher"
+            teaching = [{ subject = "数学", terms = ["七年级上学期", "九年级上学期"] }]
+        "#;
+        let profile = Profile::parse(1, text, 5, &terms()).unwrap();
+        assert_eq!(profile.role, Role::SubjectTeacher);
+        assert_eq!(profile.teaching[0].terms.len(), 2);
+        assert_eq!(profile.warnings.len(), 1);
+
+        let text = r#"teaching = [{ subject = "数学" }]"#;
+        let profile = Profile::parse(1, text, 5, &terms()).unwrap();
+        assert_eq!(profile.role, Role::Student);
+        assert_eq!(profile.warnings.len(), 1);
+    }
+}
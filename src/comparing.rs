@@ -0,0 +1,255 @@
+use crate::choosing::Profile;
+use crate::{visiting, ChoosingState, Message, Stage, State};
+use iced::widget::{self, column, container, horizontal_space, row, scrollable, text};
+use iced::{Alignment, Color, Element, Length};
+
+/// pick_list 中的一个人。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Candidate {
+    pub num: usize,
+    pub name: String,
+}
+
+impl std::fmt::Display for Candidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+pub fn candidates(choosing: &ChoosingState, chosen: usize) -> Vec<Candidate> {
+    (1..choosing.avatars.len())
+        .filter(|&num| num != chosen)
+        .map(|num| Candidate {
+            num,
+            name: choosing.avatars[num].name.clone(),
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    pub with: usize,
+    /// 两个人都在的照片
+    pub shared: Vec<visiting::Photo>,
+}
+
+/// 与 [`visiting::photos_of`] 一致，只是要两个人都在。
+fn shared_photos(queue_table: &toml::Table, a: usize, b: usize) -> Vec<visiting::Photo> {
+    let of_b = visiting::photos_of(queue_table, b);
+    visiting::photos_of(queue_table, a)
+        .into_iter()
+        .filter(|photo| of_b.iter().any(|other| other.path == photo.path))
+        .collect()
+}
+
+/// 读取 events.toml 找出两人的合照，然后打开对比页面；还没选中人时什么也不做。
+pub async fn compare(mut state: State, with: usize) -> Result<State, crate::Error> {
+    let chosen = match state.stage {
+        Stage::ChoosingCharacter(ref choosing) => choosing.on_character,
+        _ => None,
+    };
+    let Some(chosen) = chosen else {
+        return Ok(state);
+    };
+    let queue_table = visiting::read_events(&state).await;
+    if let Stage::ChoosingCharacter(ref mut choosing) = state.stage {
+        choosing.comparison = Some(Comparison {
+            with,
+            shared: shared_photos(&queue_table, chosen, with),
+        });
+    }
+    Ok(state)
+}
+
+/// 同一件事的照片合成一行，如「2021-04-02 · 春游（3 张）」。
+fn summarize(shared: &[visiting::Photo]) -> Vec<String> {
+    let mut events: Vec<(&visiting::Photo, usize)> = vec![];
+    for photo in shared {
+        match events
+            .iter_mut()
+            .find(|(first, _)| first.description == photo.description)
+        {
+            Some((_, count)) => *count += 1,
+            None => events.push((photo, 1)),
+        }
+    }
+    events
+        .into_iter()
+        .map(|(photo, count)| {
+            let date = photo
+                .date
+                .map(|date| format!("{} · ", date))
+                .unwrap_or_default();
+            if count > 1 {
+                format!("{}{}（{} 张）", date, photo.description, count)
+            } else {
+                format!("{}{}", date, photo.description)
+            }
+        })
+        .collect()
+}
+
+fn heading<'a>(title: &str) -> Element<'a, Message> {
+    text(title)
+        .size(32)
+        .style(Color::from_rgb8(120, 158, 204))
+        .into()
+}
+
+/// 一个人那一栏：昵称、关于 ta、班主任评语和表情包。
+fn side<'a>(choosing: &ChoosingState, num: usize) -> Element<'a, Message> {
+    let profile: &Profile = &choosing.profiles[num];
+    let avatar = &choosing.avatars[num];
+    let mut content = column![
+        widget::image(avatar.photo.clone()).height(Length::Fixed(200.0)),
+        text(avatar.name.clone()).size(40),
+    ]
+    .spacing(8)
+    .align_items(Alignment::Center);
+    if !profile.nickname.is_empty() {
+        content = content
+            .push(heading("昵称"))
+            .push(text(profile.nickname.join("、")).size(26));
+    }
+    if let Some(anecdote) = &profile.anecdote {
+        content = content.push(heading("关于 ta"));
+        for (question, answer) in [
+            ("兴趣爱好", &anecdote.interests),
+            ("最想做的事", &anecdote.want),
+            ("最尴尬的事", &anecdote.embarrassment),
+        ] {
+            if let Some(answer) = answer {
+                content = content.push(text(format!("{}：{}", question, answer)).size(24));
+            }
+        }
+    }
+    if !profile.reviews.is_empty() {
+        content = content.push(heading("班主任评语"));
+        for review in &profile.reviews {
+            content = content.push(
+                column![
+                    text(review.term.clone()).size(20),
+                    text(review.content.clone()).size(24)
+                ]
+                .spacing(2),
+            );
+        }
+    }
+    if !avatar.emoji.is_empty() {
+        let mut emojis = row![].spacing(5);
+        for emoji in &avatar.emoji {
+            emojis = emojis.push(
+                column![
+                    widget::image(emoji.current_frame()).height(Length::Fixed(120.0)),
+                    text(emoji.emoji_name.clone()).size(18),
+                ]
+                .align_items(Alignment::Center),
+            );
+        }
+        content = content
+            .push(heading("表情包"))
+            .push(
+                scrollable(emojis).direction(scrollable::Direction::Horizontal(
+                    scrollable::Properties::new(),
+                )),
+            );
+    }
+    content.width(Length::FillPortion(1)).into()
+}
+
+/// 两人之间的身份和评价。
+fn between(choosing: &ChoosingState, a: usize, b: usize) -> Vec<String> {
+    let mut lines = vec![];
+    for (to, from) in [(a, b), (b, a)] {
+        let profile = &choosing.profiles[to];
+        for relation in profile.relationship.iter().filter(|r| r.by == from) {
+            lines.push(format!(
+                "{} 是 {} 的 {}",
+                choosing.avatars[to].name, choosing.avatars[from].name, relation.is
+            ));
+        }
+        for comment in profile.comment.iter().filter(|c| c.from == from) {
+            lines.push(format!(
+                "{} 评价 {}：{}",
+                choosing.avatars[from].name, choosing.avatars[to].name, comment.description
+            ));
+        }
+    }
+    lines
+}
+
+pub fn show_comparison<'a>(
+    choosing: &ChoosingState,
+    chosen: usize,
+    comparison: &Comparison,
+) -> Element<'a, Message> {
+    let with = comparison.with;
+    let mut relations = column![].spacing(5);
+    let lines = between(choosing, chosen, with);
+    if lines.is_empty() {
+        relations = relations.push(text("资料里还没有写到彼此").size(24));
+    }
+    for line in lines {
+        relations = relations.push(text(line).size(28).style(Color::from_rgb8(240, 134, 80)));
+    }
+    let mut photos = column![].spacing(5);
+    for line in summarize(&comparison.shared) {
+        photos = photos.push(text(line).size(24));
+    }
+    let mut visit = widget::Button::new(text("一起回忆这些照片").size(28))
+        .style(iced::theme::Button::Primary)
+        .padding(15);
+    if !comparison.shared.is_empty() {
+        visit = visit.on_press(Message::VisitTogether);
+    }
+    let content = column![
+        row![
+            widget::Button::new(text("返回").size(28))
+                .style(iced::theme::Button::Secondary)
+                .on_press(Message::StopComparing)
+                .padding(15),
+            text(format!(
+                "{} 和 {}",
+                choosing.avatars[chosen].name, choosing.avatars[with].name
+            ))
+            .size(50),
+        ]
+        .spacing(20)
+        .align_items(Alignment::Center),
+        heading("彼此之间"),
+        row![horizontal_space(Length::Fixed(20.0)), relations],
+        heading(&format!("{} 张合照", comparison.shared.len())),
+        row![horizontal_space(Length::Fixed(20.0)), photos],
+        visit,
+        row![side(choosing, chosen), side(choosing, with)]
+            .spacing(30)
+            .align_items(Alignment::Start),
+    ]
+    .spacing(15);
+    container(scrollable(row![
+        horizontal_space(Length::FillPortion(1)),
+        content.width(Length::FillPortion(18)),
+        horizontal_space(Length::FillPortion(1)),
+    ]))
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .into()
+}
+
+/// 一起回忆时照片里要同时有这两个人。
+pub fn visit_together(choosing: &mut ChoosingState, chosen: usize, with: usize) {
+    choosing.companions = vec![chosen, with];
+    choosing.require_all = true;
+    choosing.on_character = None;
+    choosing.comparison = None;
+}
+
+pub fn pick<'a>(choosing: &ChoosingState, chosen: usize) -> Element<'a, Message> {
+    widget::pick_list(candidates(choosing, chosen), None, |candidate| {
+        Message::CompareWith(candidate.num)
+    })
+    .placeholder("和 ta 对比...")
+    .text_size(28)
+    .padding(15)
+    .into()
+}
@@ -1,6 +1,6 @@
 use crate::audio::AUDIO_PLAYER;
-use crate::{visiting, Message, Stage, State};
-use iced::widget::{self, column, container, row, text};
+use crate::{validation, visiting, Message, Stage, State};
+use iced::widget::{self, column, container, row, scrollable, text};
 use iced::{Alignment, Length, Theme};
 use std::io::Write;
 use std::sync::atomic::Ordering;
@@ -14,9 +14,12 @@ pub struct Configs {
     pub from_date: visiting::ShootingTime,
     pub volume_percentage: f32,
     pub id: iced::window::Id,
+    /// 上一次导出存档的结果
+    pub notice: String,
 }
 
 pub fn settings_over(config: Configs, content: iced::Element<Message>) -> iced::Element<Message> {
+    let warnings = validation::warnings();
     let modal = container(
         column![
             text("设置").size(38),
@@ -125,6 +128,30 @@ pub fn settings_over(config: Configs, content: iced::Element<Message>) -> iced::
                     ]
                 ]
                 .spacing(10),
+                text("导出").size(32),
+                row![
+                    widget::button(text("静态网站").size(24)).on_press(Message::ExportSite),
+                    widget::button(text("电子书").size(24)).on_press(Message::ExportBook),
+                    widget::button(text("日历").size(24)).on_press(Message::ExportCalendar(None)),
+                ]
+                .spacing(10),
+                text(&config.notice).size(20),
+                text(if warnings.is_empty() {
+                    String::from("内容校验：没有发现问题")
+                } else {
+                    format!("内容校验：{} 条提醒", warnings.len())
+                })
+                .size(24),
+                scrollable(
+                    widget::Column::with_children(
+                        warnings
+                            .into_iter()
+                            .map(|warning| text(warning).size(16).into())
+                            .collect(),
+                    )
+                    .spacing(4),
+                )
+                .height(Length::Fixed(120.0)),
                 widget::button(text("设置好啦！").size(32)).on_press(Message::HideSettings)
             ]
             .align_items(Alignment::End)
@@ -162,6 +189,8 @@ pub fn save_configs(state: &mut State) {
         }
         Stage::ShowingPlots(_) => "ShowingPlots",
         Stage::Graduated(_) => "Graduated",
+        // 统计页是从开头进去的，下次打开回到开头
+        Stage::Statistics(_) => "EntryEvents",
     }
     .to_string();
     map.insert(String::from("stage"), toml::Value::String(stage));
@@ -198,7 +227,7 @@ pub fn save_configs(state: &mut State) {
         .write_all(toml::to_string_pretty(&map).unwrap().as_bytes())
         .unwrap();
 }
-mod modal {
+pub mod modal {
     use iced::alignment::Alignment;
     use iced::event;
     use iced::mouse;
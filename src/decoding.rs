@@ -0,0 +1,481 @@
+use crate::visiting::ShootingTime;
+use iced::widget::image;
+use iced_core::image::Data;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 解码后的图片最多占用的内存（字节），学校电脑的内存可不大
+pub const CACHE_BUDGET: usize = 384 * 1024 * 1024;
+
+/// 动图的播放由定时订阅推动，每次前进这么多
+pub const ANIMATION_TICK: Duration = Duration::from_millis(30);
+
+pub static DECODED_IMAGES: Mutex<DecodedCache> = Mutex::new(DecodedCache::new(CACHE_BUDGET));
+/// 每张照片的旋转与明暗调整，键为图片在数据目录里的相对路径，数据目录搬走了也不会丢
+pub static ADJUSTMENTS: Mutex<BTreeMap<String, Adjustment>> = Mutex::new(BTreeMap::new());
+/// 数据目录，[`load_adjustments`] 时记下，用来把本地路径换成 [`ADJUSTMENTS`] 的键
+static STORAGE: Mutex<String> = Mutex::new(String::new());
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Adjustment {
+    /// 顺时针旋转的角度，只会是 0、90、180、270
+    #[serde(default)]
+    pub rotation: u16,
+    #[serde(default)]
+    pub brightness: i32,
+    #[serde(default)]
+    pub contrast: f32,
+}
+
+/// 按最近使用顺序淘汰的已解码图片缓存。
+#[derive(Debug)]
+pub struct DecodedCache {
+    budget: usize,
+    occupied: usize,
+    /// 每用一次加一，记录各张图片最后一次被用到的先后
+    clock: u64,
+    entries: BTreeMap<String, (image::Handle, usize, u64)>,
+    // 序号越小的越久没用到
+    recency: BTreeMap<u64, String>,
+}
+
+impl DecodedCache {
+    pub const fn new(budget: usize) -> Self {
+        DecodedCache {
+            budget,
+            occupied: 0,
+            clock: 0,
+            entries: BTreeMap::new(),
+            recency: BTreeMap::new(),
+        }
+    }
+
+    fn touch(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    pub fn get(&mut self, path: &str) -> Option<image::Handle> {
+        let clock = self.touch();
+        let (handle, _, used) = self.entries.get_mut(path)?;
+        let previous = std::mem::replace(used, clock);
+        let handle = handle.clone();
+        let key = self.recency.remove(&previous).unwrap();
+        self.recency.insert(clock, key);
+        Some(handle)
+    }
+
+    pub fn insert(&mut self, path: String, handle: image::Handle, size: usize) {
+        self.remove(&path);
+        let clock = self.touch();
+        self.occupied += size;
+        self.recency.insert(clock, path.clone());
+        self.entries.insert(path, (handle, size, clock));
+        // 至少留下刚放进去的这张
+        while self.occupied > self.budget && self.entries.len() > 1 {
+            let (_, oldest) = self.recency.pop_first().unwrap();
+            let (_, freed, _) = self.entries.remove(&oldest).unwrap();
+            self.occupied -= freed;
+        }
+    }
+
+    pub fn remove(&mut self, path: &str) {
+        if let Some((_, freed, used)) = self.entries.remove(path) {
+            self.recency.remove(&used);
+            self.occupied -= freed;
+        }
+    }
+
+    pub fn occupied(&self) -> usize {
+        self.occupied
+    }
+}
+
+fn adjustments_path(storage: &str) -> String {
+    format!("{}/adjustments.toml", storage)
+}
+
+/// 本地路径在数据目录里的相对路径，不在数据目录里的原样返回。
+fn relative_to(storage: &str, path: &str) -> String {
+    match path.strip_prefix(storage) {
+        Some(relative) if !storage.is_empty() => relative.trim_start_matches('/').to_string(),
+        _ => path.to_string(),
+    }
+}
+
+fn key_of(path: &str) -> String {
+    relative_to(&STORAGE.lock().unwrap(), path)
+}
+
+pub fn load_adjustments(storage: &str) {
+    *STORAGE.lock().unwrap() = storage.to_string();
+    if let Ok(text) = std::fs::read_to_string(adjustments_path(storage)) {
+        if let Ok(adjustments) = toml::from_str::<BTreeMap<String, Adjustment>>(&text) {
+            // 以前按完整路径记录，顺便换成相对路径
+            *ADJUSTMENTS.lock().unwrap() = adjustments
+                .into_iter()
+                .map(|(path, adjustment)| (relative_to(storage, &path), adjustment))
+                .collect();
+        }
+    }
+}
+
+fn save_adjustments(storage: &str) -> Result<(), String> {
+    let text = toml::to_string_pretty(&*ADJUSTMENTS.lock().unwrap()).map_err(|e| e.to_string())?;
+    let mut buffer = std::fs::File::create(adjustments_path(storage)).map_err(|e| e.to_string())?;
+    buffer.write_all(text.as_bytes()).map_err(|e| e.to_string())
+}
+
+pub fn adjustment_of(path: &str) -> Adjustment {
+    ADJUSTMENTS
+        .lock()
+        .unwrap()
+        .get(&key_of(path))
+        .copied()
+        .unwrap_or_default()
+}
+
+pub fn modify_adjustment(path: &str, modify: impl FnOnce(&mut Adjustment)) {
+    let key = key_of(path);
+    let mut adjustments = ADJUSTMENTS.lock().unwrap();
+    let adjustment = adjustments.entry(key.clone()).or_default();
+    modify(adjustment);
+    if *adjustment == Adjustment::default() {
+        adjustments.remove(&key);
+    }
+}
+
+/// 保存调整，之前解码好的版本随之作废。保存失败时调整仍然生效，只是下次打开就没了。
+pub fn commit_adjustment(storage: &str, path: &str) -> Result<(), String> {
+    DECODED_IMAGES.lock().unwrap().remove(path);
+    save_adjustments(storage)
+}
+
+pub async fn redecode(path: String) -> (String, image::Handle) {
+    let handle = tokio::task::spawn_blocking({
+        let path = path.clone();
+        move || decode(&path)
+    })
+    .await
+    .unwrap();
+    (path, handle)
+}
+
+/// 照片顺时针旋转 `rotation` 度以后，按比例给出的区域跑到了哪里。
+pub fn rotate_region(rect: iced::Rectangle, rotation: u16) -> iced::Rectangle {
+    match rotation {
+        90 => iced::Rectangle {
+            x: 1.0 - rect.y - rect.height,
+            y: rect.x,
+            width: rect.height,
+            height: rect.width,
+        },
+        180 => iced::Rectangle {
+            x: 1.0 - rect.x - rect.width,
+            y: 1.0 - rect.y - rect.height,
+            ..rect
+        },
+        270 => iced::Rectangle {
+            x: rect.y,
+            y: 1.0 - rect.x - rect.width,
+            width: rect.height,
+            height: rect.width,
+        },
+        _ => rect,
+    }
+}
+
+fn apply_adjustment(
+    mut decoded: ::image::DynamicImage,
+    adjustment: Adjustment,
+) -> ::image::DynamicImage {
+    decoded = match adjustment.rotation {
+        90 => decoded.rotate90(),
+        180 => decoded.rotate180(),
+        270 => decoded.rotate270(),
+        _ => decoded,
+    };
+    if adjustment.brightness != 0 {
+        decoded = decoded.brighten(adjustment.brightness);
+    }
+    if adjustment.contrast != 0.0 {
+        decoded = decoded.adjust_contrast(adjustment.contrast);
+    }
+    decoded
+}
+
+fn to_handle(path: String, decoded: ::image::DynamicImage) -> image::Handle {
+    let rgba = decoded.to_rgba8();
+    let (width, height) = rgba.dimensions();
+    let pixels = rgba.into_raw();
+    let size = pixels.len();
+    let handle = image::Handle::from_pixels(width, height, pixels);
+    DECODED_IMAGES
+        .lock()
+        .unwrap()
+        .insert(path, handle.clone(), size);
+    handle
+}
+
+fn read_exif(bytes: &[u8]) -> Option<exif::Exif> {
+    exif::Reader::new()
+        .read_from_container(&mut std::io::Cursor::new(bytes))
+        .ok()
+}
+
+/// 按照 EXIF 中的 Orientation 把照片转正，取值含义见 EXIF 2.32 标准。
+fn apply_orientation(decoded: ::image::DynamicImage, orientation: u32) -> ::image::DynamicImage {
+    match orientation {
+        2 => decoded.fliph(),
+        3 => decoded.rotate180(),
+        4 => decoded.flipv(),
+        5 => decoded.rotate90().fliph(),
+        6 => decoded.rotate90(),
+        7 => decoded.rotate270().fliph(),
+        8 => decoded.rotate270(),
+        _ => decoded,
+    }
+}
+
+fn orientation_of(bytes: &[u8]) -> u32 {
+    read_exif(bytes)
+        .and_then(|info| {
+            info.get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+                .and_then(|field| field.value.get_uint(0))
+        })
+        .unwrap_or(1)
+}
+
+/// 读取照片的拍摄时间，优先使用 DateTimeOriginal。
+pub fn shooting_time(bytes: &[u8]) -> Option<ShootingTime> {
+    shooting_time_in(&read_exif(bytes)?)
+}
+
+/// 与 [`shooting_time`] 相同，但只读文件开头的 EXIF，不把整张照片读进内存。
+pub fn shooting_time_of_file(path: &str) -> Option<ShootingTime> {
+    let file = std::fs::File::open(path).ok()?;
+    let info = exif::Reader::new()
+        .read_from_container(&mut std::io::BufReader::new(file))
+        .ok()?;
+    shooting_time_in(&info)
+}
+
+fn shooting_time_in(info: &exif::Exif) -> Option<ShootingTime> {
+    let field = info
+        .get_field(exif::Tag::DateTimeOriginal, exif::In::PRIMARY)
+        .or_else(|| info.get_field(exif::Tag::DateTime, exif::In::PRIMARY))?;
+    let exif::Value::Ascii(ref ascii) = field.value else {
+        return None;
+    };
+    let shot = exif::DateTime::from_ascii(ascii.first()?).ok()?;
+    let date = time::Date::from_calendar_date(
+        shot.year as i32,
+        time::Month::try_from(shot.month).ok()?,
+        shot.day,
+    )
+    .ok()?;
+    let clock = time::Time::from_hms(shot.hour, shot.minute, shot.second).ok()?;
+    Some(ShootingTime::Precise(time::PrimitiveDateTime::new(
+        date, clock,
+    )))
+}
+
+/// 把磁盘上的图片解码为 RGBA，解码失败时交给渲染器自己处理。
+pub fn decode(path: &str) -> image::Handle {
+    if let Some(handle) = DECODED_IMAGES.lock().unwrap().get(path) {
+        return handle;
+    }
+    match std::fs::read(path) {
+        Ok(bytes) => decode_bytes(path, &bytes),
+        Err(_) => image::Handle::from_path(path),
+    }
+}
+
+/// 与 [`decode`] 相同，只是图片刚刚下载下来，还在内存里。
+pub fn decode_bytes(path: &str, bytes: &[u8]) -> image::Handle {
+    if let Some(handle) = DECODED_IMAGES.lock().unwrap().get(path) {
+        return handle;
+    }
+    match ::image::load_from_memory(bytes) {
+        Ok(decoded) => to_handle(
+            path.to_string(),
+            apply_adjustment(
+                apply_orientation(decoded, orientation_of(bytes)),
+                adjustment_of(path),
+            ),
+        ),
+        Err(_) => image::Handle::from_path(path),
+    }
+}
+
+pub fn path_of(handle: &image::Handle) -> Option<String> {
+    match handle.data() {
+        Data::Path(path) => Some(path.display().to_string()),
+        _ => None,
+    }
+}
+
+/// 如果这张图片已经在后台解码好了，就换成解码后的版本。
+pub fn lookup(handle: &image::Handle) -> image::Handle {
+    if let Some(path) = path_of(handle) {
+        if let Some(decoded) = DECODED_IMAGES.lock().unwrap().get(&path) {
+            return decoded;
+        }
+    }
+    handle.clone()
+}
+
+pub async fn prefetch(paths: Vec<String>) {
+    let mut threads = vec![];
+    for path in paths {
+        threads.push(tokio::task::spawn_blocking(move || {
+            decode(&path);
+        }));
+    }
+    for t in threads {
+        let _ = t.await;
+    }
+}
+
+/// 解码好的动图（GIF / WebP），每一帧都已经是 RGBA。
+#[derive(Clone, Debug)]
+pub struct Animation {
+    frames: Vec<(image::Handle, Duration)>,
+    total: Duration,
+    playhead: Duration,
+}
+
+impl Animation {
+    pub fn current_frame(&self) -> image::Handle {
+        let mut passed = Duration::ZERO;
+        for (frame, delay) in &self.frames {
+            passed += *delay;
+            if self.playhead < passed {
+                return frame.clone();
+            }
+        }
+        self.frames.last().unwrap().0.clone()
+    }
+
+    pub fn advance(&mut self) {
+        self.playhead += ANIMATION_TICK;
+        if self.playhead >= self.total {
+            self.playhead =
+                Duration::from_nanos((self.playhead.as_nanos() % self.total.as_nanos()) as u64);
+        }
+    }
+}
+
+pub fn may_be_animated(path: &str) -> bool {
+    let lowercase = path.to_lowercase();
+    lowercase.ends_with(".gif") || lowercase.ends_with(".webp")
+}
+
+/// 解码动图的所有帧；静态图片或者只有一帧时返回 `None`。
+pub fn decode_animation(bytes: &[u8]) -> Option<Animation> {
+    use ::image::codecs::gif::GifDecoder;
+    use ::image::codecs::webp::WebPDecoder;
+    use ::image::AnimationDecoder;
+    let cursor = std::io::Cursor::new(bytes);
+    let frames = match ::image::guess_format(bytes).ok()? {
+        ::image::ImageFormat::Gif => GifDecoder::new(cursor).ok()?.into_frames(),
+        ::image::ImageFormat::WebP => {
+            let decoder = WebPDecoder::new(cursor).ok()?;
+            if !decoder.has_animation() {
+                return None;
+            }
+            decoder.into_frames()
+        }
+        _ => return None,
+    }
+    .collect_frames()
+    .ok()?;
+    if frames.len() < 2 {
+        return None;
+    }
+    let mut decoded = Vec::with_capacity(frames.len());
+    let mut total = Duration::ZERO;
+    for frame in frames {
+        let mut delay: Duration = frame.delay().into();
+        // 和浏览器一样，过短的间隔按 100ms 处理
+        if delay < Duration::from_millis(20) {
+            delay = Duration::from_millis(100);
+        }
+        total += delay;
+        let buffer = frame.into_buffer();
+        let (width, height) = buffer.dimensions();
+        decoded.push((
+            image::Handle::from_pixels(width, height, buffer.into_raw()),
+            delay,
+        ));
+    }
+    Some(Animation {
+        frames: decoded,
+        total,
+        playhead: Duration::ZERO,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handle() -> image::Handle {
+        image::Handle::from_pixels(1, 1, vec![0; 4])
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = DecodedCache::new(10);
+        cache.insert(String::from("a"), handle(), 4);
+        cache.insert(String::from("b"), handle(), 4);
+        assert!(cache.get("a").is_some());
+        cache.insert(String::from("c"), handle(), 4);
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("c").is_some());
+        assert_eq!(cache.occupied(), 8);
+    }
+
+    #[test]
+    fn keeps_the_newest_even_over_budget() {
+        let mut cache = DecodedCache::new(10);
+        cache.insert(String::from("a"), handle(), 4);
+        cache.insert(String::from("huge"), handle(), 30);
+        assert!(cache.get("a").is_none());
+        assert!(cache.get("huge").is_some());
+        assert_eq!(cache.occupied(), 30);
+    }
+
+    #[test]
+    fn reinserting_replaces_the_size() {
+        let mut cache = DecodedCache::new(10);
+        cache.insert(String::from("a"), handle(), 4);
+        cache.insert(String::from("a"), handle(), 6);
+        assert_eq!(cache.occupied(), 6);
+        cache.remove("a");
+        assert_eq!(cache.occupied(), 0);
+        assert!(cache.get("a").is_none());
+    }
+
+    #[test]
+    fn adjustments_are_keyed_inside_the_data_directory() {
+        let storage = "/home/a/.local/share/graduate";
+        assert_eq!(
+            relative_to(
+                storage,
+                "/home/a/.local/share/graduate/image/experience/1.jpg"
+            ),
+            "image/experience/1.jpg"
+        );
+        assert_eq!(
+            relative_to(storage, "/home/a/.local/share/graduateimage/1.jpg"),
+            "image/1.jpg"
+        );
+        assert_eq!(relative_to(storage, "image/1.jpg"), "image/1.jpg");
+        assert_eq!(relative_to("", "/tmp/1.jpg"), "/tmp/1.jpg");
+    }
+}
@@ -0,0 +1,74 @@
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
+use image::{Rgba, RgbaImage};
+
+/// 一行字按 `size` 像素的字号排开后的宽度。
+pub fn text_width(font: &FontRef, size: f32, content: &str) -> f32 {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let mut width = 0.0;
+    let mut previous = None;
+    for c in content.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            width += scaled.kern(previous, id);
+        }
+        width += scaled.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+/// 放不下时从末尾截断并加上省略号。
+pub fn fit(font: &FontRef, size: f32, content: &str, max_width: f32) -> String {
+    if text_width(font, size, content) <= max_width {
+        return content.to_string();
+    }
+    let mut chars: Vec<char> = content.chars().collect();
+    while !chars.is_empty() {
+        chars.pop();
+        let shortened = format!("{}…", chars.iter().collect::<String>());
+        if text_width(font, size, &shortened) <= max_width {
+            return shortened;
+        }
+    }
+    String::new()
+}
+
+/// 以 `top` 为文字顶端，水平居中绘制一行字。
+pub fn draw_centered(
+    canvas: &mut RgbaImage,
+    font: &FontRef,
+    size: f32,
+    content: &str,
+    top: f32,
+    color: Rgba<u8>,
+) {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let content = fit(font, size, content, canvas.width() as f32 * 0.92);
+    let mut x = (canvas.width() as f32 - text_width(font, size, &content)) / 2.0;
+    let baseline = top + scaled.ascent();
+    let mut previous = None;
+    for c in content.chars() {
+        let mut glyph = scaled.scaled_glyph(c);
+        if let Some(previous) = previous {
+            x += scaled.kern(previous, glyph.id);
+        }
+        previous = Some(glyph.id);
+        glyph.position = ab_glyph::point(x, baseline);
+        x += scaled.h_advance(glyph.id);
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i64 + gx as i64;
+            let py = bounds.min.y as i64 + gy as i64;
+            if px < 0 || py < 0 || px >= canvas.width() as i64 || py >= canvas.height() as i64 {
+                return;
+            }
+            let pixel = canvas.get_pixel_mut(px as u32, py as u32);
+            for (channel, tint) in pixel.0.iter_mut().zip(color.0).take(3) {
+                *channel = (*channel as f32 * (1.0 - coverage) + tint as f32 * coverage) as u8;
+            }
+        });
+    }
+}
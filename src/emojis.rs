@@ -0,0 +1,271 @@
+use crate::choosing::{Avatar, Emoji};
+use crate::export::{save_with_notice, Destination};
+use crate::{ChoosingState, Message};
+use iced::widget::{self, column, container, row, scrollable, text, text_input};
+use iced::{Alignment, Element, Length};
+use std::borrow::Cow;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+#[cfg(target_os = "linux")]
+use std::sync::Mutex;
+
+const PER_ROW: usize = 6;
+
+/// 所有同学的表情包，可以按表情名或主人搜索。
+#[derive(Clone, Debug, Default)]
+pub struct Browser {
+    pub query: String,
+    /// 上一次复制或导出的结果
+    pub notice: String,
+}
+
+fn matches(avatar: &Avatar, emoji: &Emoji, query: &str) -> bool {
+    query.is_empty()
+        || emoji.emoji_name.to_lowercase().contains(query)
+        || avatar.name.contains(query)
+        || avatar.search_keys.iter().any(|key| key.contains(query))
+}
+
+/// 表情名和人名都是内容作者写的，去掉路径分隔符等文件名里不能用的字符。
+fn sanitize(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.trim().trim_matches('.');
+    if cleaned.is_empty() {
+        String::from("表情")
+    } else {
+        cleaned.to_string()
+    }
+}
+
+/// 文件名里带上扩展名，方便聊天软件识别。
+fn file_name(emoji: &Emoji) -> String {
+    let name = sanitize(&emoji.emoji_name);
+    match Path::new(&emoji.path).extension() {
+        Some(extension) => format!("{}.{}", name, extension.to_string_lossy()),
+        None => name,
+    }
+}
+
+/// 重名的加上序号，如「哈哈.png」「哈哈-2.png」，免得压缩包里互相覆盖。
+fn deduplicate(names: Vec<String>) -> Vec<String> {
+    let mut taken: Vec<String> = vec![];
+    for name in names {
+        let path = Path::new(&name);
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let extension = path
+            .extension()
+            .map(|extension| format!(".{}", extension.to_string_lossy()))
+            .unwrap_or_default();
+        let mut unique = name.clone();
+        let mut index = 2;
+        while taken.contains(&unique) {
+            unique = format!("{}-{}{}", stem, index, extension);
+            index += 1;
+        }
+        taken.push(unique);
+    }
+    taken
+}
+
+/// Linux 上剪贴板里的内容要由复制的程序自己提供，`Clipboard` 释放后就粘贴不出来了，
+/// 所以留着最近一次用的。
+#[cfg(target_os = "linux")]
+static CLIPBOARD: Mutex<Option<arboard::Clipboard>> = Mutex::new(None);
+
+/// 剪贴板只支持静态图片，动图只会复制第一帧。
+pub async fn copy_image(path: String) -> String {
+    let copied = tokio::task::spawn_blocking(move || -> Result<(), String> {
+        let rgba = image::open(&path).map_err(|e| e.to_string())?.to_rgba8();
+        let mut clipboard = arboard::Clipboard::new().map_err(|e| e.to_string())?;
+        clipboard
+            .set_image(arboard::ImageData {
+                width: rgba.width() as usize,
+                height: rgba.height() as usize,
+                bytes: Cow::from(rgba.into_raw()),
+            })
+            .map_err(|e| e.to_string())?;
+        #[cfg(target_os = "linux")]
+        CLIPBOARD.lock().unwrap().replace(clipboard);
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|copied| copied);
+    match copied {
+        Ok(()) => String::from("已复制到剪贴板"),
+        Err(err) => format!("复制失败：{}", err),
+    }
+}
+
+pub async fn save_as(path: String, name: String) -> String {
+    let destination = Destination::Folder {
+        title: "保存表情到...",
+    };
+    save_with_notice(destination, |folder| async move {
+        fs::copy(&path, folder.join(&name)).map_err(|e| e.to_string())?;
+        Ok::<_, String>(format!("，文件名为 {}", name))
+    })
+    .await
+}
+
+/// 把一个人的表情包打包成 zip，可以直接导入聊天软件。
+pub async fn export_stickers(owner: String, emojis: Vec<(String, String)>) -> String {
+    let destination = Destination::Folder {
+        title: "导出表情包到...",
+    };
+    save_with_notice(destination, |folder| async move {
+        let name = format!("{}的表情包.zip", sanitize(&owner));
+        let target = folder.join(&name);
+        tokio::task::spawn_blocking(move || -> zip::result::ZipResult<()> {
+            let mut writer = zip::ZipWriter::new(fs::File::create(target)?);
+            let options = zip::write::FileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated);
+            for (path, name) in emojis {
+                writer.start_file(name, options)?;
+                writer.write_all(&fs::read(path)?)?;
+            }
+            writer.finish()?;
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+        Ok::<_, String>(format!("，文件名为 {}", name))
+    })
+    .await
+}
+
+/// 一个人的全部表情：(本地路径, 压缩包内的文件名)
+pub fn sticker_files(avatar: &Avatar) -> Vec<(String, String)> {
+    let names = deduplicate(avatar.emoji.iter().map(file_name).collect());
+    avatar
+        .emoji
+        .iter()
+        .map(|emoji| emoji.path.clone())
+        .zip(names)
+        .collect()
+}
+
+pub fn emoji_file(emoji: &Emoji) -> (String, String) {
+    (emoji.path.clone(), file_name(emoji))
+}
+
+fn card<'a>(owner: usize, index: usize, emoji: &Emoji) -> Element<'a, Message> {
+    column![
+        widget::image(emoji.current_frame()).height(Length::Fixed(150.0)),
+        text(emoji.emoji_name.clone()).size(22),
+        row![
+            widget::Button::new(text("复制").size(18))
+                .style(iced::theme::Button::Secondary)
+                .on_press(Message::CopyEmoji(owner, index)),
+            widget::Button::new(text("另存为").size(18))
+                .style(iced::theme::Button::Secondary)
+                .on_press(Message::SaveEmoji(owner, index)),
+        ]
+        .spacing(5),
+    ]
+    .spacing(5)
+    .align_items(Alignment::Center)
+    .width(Length::FillPortion(1))
+    .into()
+}
+
+pub fn show_emojis<'a>(choosing: &ChoosingState, browser: &Browser) -> Element<'a, Message> {
+    let query = browser.query.trim().to_lowercase();
+    let mut owners = column![].spacing(20);
+    let mut found = 0;
+    for (owner, avatar) in choosing.avatars.iter().enumerate().skip(1) {
+        let cards: Vec<Element<Message>> = avatar
+            .emoji
+            .iter()
+            .enumerate()
+            .filter(|(_, emoji)| matches(avatar, emoji, &query))
+            .map(|(index, emoji)| card(owner, index, emoji))
+            .collect();
+        if cards.is_empty() {
+            continue;
+        }
+        found += cards.len();
+        let mut grid = column![].spacing(10);
+        let mut cards = cards.into_iter().peekable();
+        while cards.peek().is_some() {
+            let mut line = row![].spacing(10);
+            for _ in 0..PER_ROW {
+                line = line.push(match cards.next() {
+                    Some(card) => card,
+                    None => widget::horizontal_space(Length::FillPortion(1)).into(),
+                });
+            }
+            grid = grid.push(line);
+        }
+        owners = owners.push(column![
+            row![
+                widget::Button::new(text(avatar.name.clone()).size(34))
+                    .style(iced::theme::Button::Text)
+                    .on_press(Message::ChoseCharacter(owner)),
+                widget::Button::new(text("导出表情包").size(20))
+                    .style(iced::theme::Button::Secondary)
+                    .on_press(Message::ExportStickers(owner)),
+            ]
+            .spacing(15)
+            .align_items(Alignment::Center),
+            grid,
+        ]);
+    }
+    if found == 0 {
+        owners = owners.push(text("没有找到表情").size(28));
+    }
+    let toolbar = row![
+        widget::Button::new(text("返回").size(28))
+            .style(iced::theme::Button::Secondary)
+            .on_press(Message::BackStage)
+            .padding(15),
+        widget::Button::new(text("头像列表").size(28))
+            .style(iced::theme::Button::Secondary)
+            .on_press(Message::ToggleEmojis)
+            .padding(15),
+        text_input("按表情名或同学搜索", &browser.query)
+            .on_input(Message::EmojiSearched)
+            .size(28)
+            .padding(15),
+        text(&browser.notice).size(20),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+    container(column![toolbar, scrollable(owners).height(Length::Fill)].spacing(10))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_strips_path_tricks() {
+        assert_eq!(sanitize("../../etc/passwd"), "_.._etc_passwd");
+        assert_eq!(sanitize("a\\b:c"), "a_b_c");
+        assert_eq!(sanitize(" .. "), "表情");
+        assert_eq!(sanitize("哈哈"), "哈哈");
+    }
+
+    #[test]
+    fn deduplicate_adds_numbers() {
+        let names = ["哈哈.png", "哈哈.png", "哈哈-2.png", "哈哈.png", "呜"]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(
+            deduplicate(names),
+            ["哈哈.png", "哈哈-2.png", "哈哈-2-2.png", "哈哈-3.png", "呜"]
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use crate::audio;
 use crate::configs::Configs;
 use crate::visiting::ShootingTime;
-use crate::{choosing, EntryState, Stage, State};
+use crate::{choosing, terms, validation, EntryState, Stage, State};
 use iced::widget::image;
 use iced::Theme;
 use reqwest::Client;
@@ -21,6 +21,7 @@ impl State {
         let idxurl = String::from("https://yankang1.coding.net/p/graduate/shared-depot/graduate/git/raw/gh-pages/index.toml");
         let content = if let None = reusable {
             fs::create_dir_all(&storage).unwrap();
+            crate::decoding::load_adjustments(&storage);
             fs::create_dir_all(proj_dir.config_dir().display().to_string()).unwrap();
             let cli = Client::new().to_owned();
             if let Ok(fetching) = cli.get(&idxurl).send().await {
@@ -40,6 +41,9 @@ impl State {
             .as_table()
             .unwrap()
             .to_owned();
+        let mut warnings = vec![];
+        let terms = terms::read_terms(&idxtable, &mut warnings);
+        validation::report(&warnings);
         let together_events = idxtable
             .get("together_event")
             .unwrap()
@@ -123,7 +127,8 @@ impl State {
                     );
                     let mut file = std::fs::File::create(&img_dir).unwrap();
                     file.write_all(&bytes).unwrap();
-                    fillin.push(image::Handle::from_memory(bytes));
+                    // 只保留路径，真正解码交给 `decoding` 在后台完成
+                    fillin.push(image::Handle::from_path(&img_dir));
                 }
                 let mut images = img_mutex.lock().unwrap();
 
@@ -213,6 +218,7 @@ impl State {
                 shown: false,
                 full_screened: false,
                 id: iced::window::Id::unique(),
+                notice: String::new(),
             };
             let stage = match config_table.get("stage").unwrap().as_str().unwrap() {
                 "ChoosingCharacter" => {
@@ -237,8 +243,11 @@ impl State {
                                 ..Default::default()
                             }),
                             idxtable,
+                            terms,
                             storage,
                             configs,
+                            search: Default::default(),
+                            qr: None,
                         },
                     )
                     .await
@@ -251,8 +260,11 @@ impl State {
                             ..Default::default()
                         }),
                         idxtable,
+                        terms,
                         storage,
                         configs,
+                        search: Default::default(),
+                        qr: None,
                     })
                     .await
                     .unwrap();
@@ -266,8 +278,11 @@ impl State {
             Ok(State {
                 stage,
                 idxtable,
+                terms,
                 storage,
                 configs,
+                search: Default::default(),
+                qr: None,
             })
         } else {
             std::thread::spawn(|| {
@@ -279,6 +294,7 @@ impl State {
                     ..Default::default()
                 }),
                 idxtable,
+                terms,
                 storage,
                 configs: Configs {
                     theme: Theme::Light,
@@ -290,10 +306,24 @@ impl State {
                     shown: false,
                     full_screened: false,
                     id: iced::window::Id::unique(),
+                    notice: String::new(),
                 },
+                search: Default::default(),
+                qr: None,
             })
         }
     }
+    pub fn get_name(&self, num: usize) -> String {
+        self.idxtable
+            .get("profile")
+            .unwrap()
+            .as_table()
+            .unwrap()
+            .get(&num.to_string())
+            .and_then(|name| name.as_str())
+            .unwrap_or_default()
+            .to_string()
+    }
     pub fn get_current_event(&self, on_event: usize) -> toml::value::Value {
         self.idxtable
             .get("together_event")
@@ -0,0 +1,56 @@
+use std::future::Future;
+use std::path::PathBuf;
+
+/// 导出到哪里。
+pub enum Destination {
+    /// 让用户选一个文件，`filter` 为（类型名，扩展名）
+    File {
+        title: &'static str,
+        name: String,
+        filter: (&'static str, &'static [&'static str]),
+    },
+    /// 让用户选一个文件夹
+    Folder { title: &'static str },
+    /// 不用选，直接写到这里
+    Fixed(PathBuf),
+}
+
+impl Destination {
+    async fn pick(self) -> Option<PathBuf> {
+        match self {
+            Destination::File {
+                title,
+                name,
+                filter,
+            } => rfd::AsyncFileDialog::new()
+                .set_title(title)
+                .set_file_name(name)
+                .add_filter(filter.0, filter.1)
+                .save_file()
+                .await
+                .map(|file| file.path().to_path_buf()),
+            Destination::Folder { title } => rfd::AsyncFileDialog::new()
+                .set_title(title)
+                .pick_folder()
+                .await
+                .map(|folder| folder.path().to_path_buf()),
+            Destination::Fixed(path) => Some(path),
+        }
+    }
+}
+
+/// 选好位置后交给 `write`，它成功时返回接在提示后面的补充说明（可以为空）。
+/// 用户取消时返回空字符串，否则返回导出到了哪里或者为什么失败。
+pub async fn save_with_notice<W, F>(destination: Destination, write: W) -> String
+where
+    W: FnOnce(PathBuf) -> F,
+    F: Future<Output = Result<String, String>>,
+{
+    let Some(path) = destination.pick().await else {
+        return String::new();
+    };
+    match write(path.clone()).await {
+        Ok(detail) => format!("已导出到 {}{}", path.display(), detail),
+        Err(err) => format!("导出失败：{}", err),
+    }
+}
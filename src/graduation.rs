@@ -108,6 +108,7 @@ pub async fn load_map(state: State) -> Result<State, crate::Error> {
             show_panel: true,
             on_image: 0,
             images: pans,
+            mosaic: None,
         }),
         ..state
     })
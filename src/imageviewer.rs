@@ -2,6 +2,7 @@
 use iced::widget::scrollable::RelativeOffset;
 use iced_core::event::{self, Event};
 use iced_core::image;
+use iced_core::keyboard::{self, KeyCode};
 use iced_core::layout;
 use iced_core::mouse;
 use iced_core::renderer;
@@ -9,7 +10,8 @@ use iced_core::widget;
 use iced_core::widget::operation;
 use iced_core::widget::tree::{self, Tree};
 use iced_core::{
-    Clipboard, Element, Layout, Length, Pixels, Point, Rectangle, Shell, Size, Vector, Widget,
+    Clipboard, Color, Element, Layout, Length, Pixels, Point, Rectangle, Shell, Size, Vector,
+    Widget,
 };
 use std::hash::Hash;
 
@@ -25,6 +27,7 @@ pub struct Viewer<Handle> {
     scale_step: f32,
     handle: Handle,
     filter_method: image::FilterMethod,
+    regions: Vec<(Rectangle, Color)>,
 }
 
 impl<Handle> Viewer<Handle> {
@@ -40,9 +43,17 @@ impl<Handle> Viewer<Handle> {
             scale_step: 0.10,
             filter_method: image::FilterMethod::default(),
             handle,
+            regions: vec![],
         }
     }
 
+    /// Marks regions of the image, given as fractions of its size, which
+    /// are highlighted when hovered.
+    pub fn regions(mut self, regions: Vec<(Rectangle, Color)>) -> Self {
+        self.regions = regions;
+        self
+    }
+
     /// Sets the [`Id`] of the [`Scrollable`].
     pub fn id(mut self, id: Id) -> Self {
         self.id = Some(id);
@@ -91,6 +102,55 @@ impl<Handle> Viewer<Handle> {
         self.scale_step = scale_step;
         self
     }
+
+    /// Scales the image to `scale`, keeping the point at `anchor` (relative
+    /// to the center of the [`Viewer`]) where it is.
+    fn zoom_to<Renderer>(
+        &self,
+        renderer: &Renderer,
+        state: &mut State,
+        bounds: Rectangle,
+        scale: f32,
+        anchor: Vector,
+    ) where
+        Renderer: image::Renderer<Handle = Handle>,
+    {
+        let previous_scale = state.scale;
+        state.scale = scale.clamp(self.min_scale, self.max_scale);
+
+        let image_size = image_size(renderer, &self.handle, state, bounds.size());
+
+        let factor = state.scale / previous_scale - 1.0;
+
+        let adjustment = anchor * factor + state.current_offset * factor;
+
+        state.current_offset = Vector::new(
+            if image_size.width > bounds.width {
+                state.current_offset.x + adjustment.x
+            } else {
+                0.0
+            },
+            if image_size.height > bounds.height {
+                state.current_offset.y + adjustment.y
+            } else {
+                0.0
+            },
+        );
+    }
+
+    fn pan_by<Renderer>(
+        &self,
+        renderer: &Renderer,
+        state: &mut State,
+        bounds: Rectangle,
+        delta: Vector,
+    ) where
+        Renderer: image::Renderer<Handle = Handle>,
+    {
+        let image_size = image_size(renderer, &self.handle, state, bounds.size());
+        state.current_offset = state.offset(bounds, image_size) + delta;
+        state.current_offset = state.offset(bounds, image_size);
+    }
 }
 
 impl<Message, Renderer, Handle> Widget<Message, Renderer> for Viewer<Handle>
@@ -175,35 +235,17 @@ where
                         if y < 0.0 && previous_scale > self.min_scale
                             || y > 0.0 && previous_scale < self.max_scale
                         {
-                            state.scale = (if y > 0.0 {
+                            let scale = if y > 0.0 {
                                 state.scale * (1.0 + self.scale_step)
                             } else {
                                 state.scale / (1.0 + self.scale_step)
-                            })
-                            .clamp(self.min_scale, self.max_scale);
-
-                            let image_size =
-                                image_size(renderer, &self.handle, state, bounds.size());
-
-                            let factor = state.scale / previous_scale - 1.0;
-
-                            let cursor_to_center =
-                                cursor_position.position().unwrap() - bounds.center();
-
-                            let adjustment =
-                                cursor_to_center * factor + state.current_offset * factor;
-
-                            state.current_offset = Vector::new(
-                                if image_size.width > bounds.width {
-                                    state.current_offset.x + adjustment.x
-                                } else {
-                                    0.0
-                                },
-                                if image_size.height > bounds.height {
-                                    state.current_offset.y + adjustment.y
-                                } else {
-                                    0.0
-                                },
+                            };
+                            self.zoom_to(
+                                renderer,
+                                state,
+                                bounds,
+                                scale,
+                                cursor_position.position().unwrap() - bounds.center(),
                             );
                         }
                     }
@@ -213,19 +255,93 @@ where
             }
             Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) if is_mouse_over => {
                 let state = tree.state.downcast_mut::<State>();
+                let position = cursor_position.position().unwrap();
+                let click = mouse::Click::new(position, state.last_click);
+                state.last_click = Some(click);
+                state.focused = true;
+
+                if let mouse::click::Kind::Double = click.kind() {
+                    // 双击在「适应窗口」与「原始大小」之间切换
+                    if state.scale == 1.0 && state.fit == Fit::Contain {
+                        let scale = 1.0 / fitting_ratio(renderer, &self.handle, bounds.size());
+                        self.zoom_to(renderer, state, bounds, scale, position - bounds.center());
+                    } else {
+                        state.fit = Fit::Contain;
+                        state.scale = 1.0;
+                        state.current_offset = Vector::new(0.0, 0.0);
+                    }
+                    return event::Status::Captured;
+                }
 
-                state.cursor_grabbed_at = Some(cursor_position.position().unwrap());
+                state.cursor_grabbed_at = Some(position);
                 state.starting_offset = state.current_offset;
 
                 event::Status::Captured
             }
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let state = tree.state.downcast_mut::<State>();
+                state.focused = false;
+
+                event::Status::Ignored
+            }
             Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Right)) => {
                 let state = tree.state.downcast_mut::<State>();
+                state.fit = Fit::Contain;
                 state.scale = 1.0;
                 state.current_offset = Vector::new(0.0, 0.0);
 
                 event::Status::Captured
             }
+            // 带 Ctrl 的组合键留给全局快捷键
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code,
+                modifiers,
+            }) if tree.state.downcast_ref::<State>().focused
+                && !modifiers.command()
+                && handles(key_code) =>
+            {
+                let state = tree.state.downcast_mut::<State>();
+                let step = Vector::new(bounds.width / 10.0, bounds.height / 10.0);
+                let image_size = image_size(renderer, &self.handle, state, bounds.size());
+                match key_code {
+                    // 图片没有超出这个方向时无处可移，方向键留给翻页
+                    KeyCode::Left | KeyCode::Right if image_size.width <= bounds.width => {
+                        return event::Status::Ignored
+                    }
+                    KeyCode::Up | KeyCode::Down if image_size.height <= bounds.height => {
+                        return event::Status::Ignored
+                    }
+                    KeyCode::Plus | KeyCode::NumpadAdd | KeyCode::Equals => {
+                        let scale = state.scale * (1.0 + self.scale_step);
+                        self.zoom_to(renderer, state, bounds, scale, Vector::ZERO);
+                    }
+                    KeyCode::Minus | KeyCode::NumpadSubtract => {
+                        let scale = state.scale / (1.0 + self.scale_step);
+                        self.zoom_to(renderer, state, bounds, scale, Vector::ZERO);
+                    }
+                    KeyCode::Left => {
+                        self.pan_by(renderer, state, bounds, Vector::new(-step.x, 0.0))
+                    }
+                    KeyCode::Right => {
+                        self.pan_by(renderer, state, bounds, Vector::new(step.x, 0.0))
+                    }
+                    KeyCode::Up => self.pan_by(renderer, state, bounds, Vector::new(0.0, -step.y)),
+                    KeyCode::Down => self.pan_by(renderer, state, bounds, Vector::new(0.0, step.y)),
+                    KeyCode::Key0 | KeyCode::Numpad0 => state.fit_to(Fit::Contain),
+                    KeyCode::Key1 | KeyCode::Numpad1 => {
+                        state.fit_to(Fit::Contain);
+                        let scale = 1.0 / fitting_ratio(renderer, &self.handle, bounds.size());
+                        self.zoom_to(renderer, state, bounds, scale, Vector::ZERO);
+                    }
+                    KeyCode::Key2 | KeyCode::Numpad2 => state.fit_to(Fit::Width),
+                    KeyCode::Key3 | KeyCode::Numpad3 => state.fit_to(Fit::Height),
+                    KeyCode::Key4 | KeyCode::Numpad4 => state.fit_to(Fit::Fill),
+                    KeyCode::Escape => state.focused = false,
+                    _ => return event::Status::Ignored,
+                }
+
+                event::Status::Captured
+            }
             Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
                 let state = tree.state.downcast_mut::<State>();
 
@@ -300,7 +416,7 @@ where
         _theme: &Renderer::Theme,
         _style: &renderer::Style,
         layout: Layout<'_>,
-        _cursor: mouse::Cursor,
+        cursor: mouse::Cursor,
         _viewport: &Rectangle,
     ) {
         let state = tree.state.downcast_ref::<State>();
@@ -331,6 +447,75 @@ where
                 );
             });
         });
+
+        // 照片里每个人的位置，鼠标移上去时加粗并填充
+        if !self.regions.is_empty() {
+            renderer.with_layer(bounds, |renderer| {
+                for (region, color) in &self.regions {
+                    let area = Rectangle {
+                        x: bounds.x + translation.x + region.x * image_size.width,
+                        y: bounds.y + translation.y + region.y * image_size.height,
+                        width: region.width * image_size.width,
+                        height: region.height * image_size.height,
+                    };
+                    let hovered = cursor.is_over(bounds) && cursor.is_over(area);
+                    renderer.fill_quad(
+                        renderer::Quad {
+                            bounds: area,
+                            border_radius: 4.0.into(),
+                            border_width: if hovered { 4.0 } else { 2.0 },
+                            border_color: *color,
+                        },
+                        Color {
+                            a: if hovered { 0.2 } else { 0.0 },
+                            ..*color
+                        },
+                    );
+                }
+            });
+        }
+
+        // 放大后在右下角画一张小地图，标出当前看到的区域
+        if image_size.width > bounds.width || image_size.height > bounds.height {
+            let longer_side = image_size.width.max(image_size.height);
+            let ratio = MINIMAP_SIZE / longer_side;
+            let minimap = Rectangle {
+                x: bounds.x + bounds.width - image_size.width * ratio - MINIMAP_MARGIN,
+                y: bounds.y + bounds.height - image_size.height * ratio - MINIMAP_MARGIN,
+                width: image_size.width * ratio,
+                height: image_size.height * ratio,
+            };
+            let visible = Rectangle {
+                x: minimap.x + (-translation.x).clamp(0.0, image_size.width) * ratio,
+                y: minimap.y + (-translation.y).clamp(0.0, image_size.height) * ratio,
+                width: bounds.width.min(image_size.width) * ratio,
+                height: bounds.height.min(image_size.height) * ratio,
+            };
+            renderer.with_layer(bounds, |renderer| {
+                renderer.fill_quad(
+                    renderer::Quad {
+                        bounds: minimap,
+                        border_radius: Default::default(),
+                        border_width: 1.0,
+                        border_color: Color::WHITE,
+                    },
+                    Color::BLACK,
+                );
+                image::Renderer::draw(renderer, self.handle.clone(), self.filter_method, minimap);
+                renderer.fill_quad(
+                    renderer::Quad {
+                        bounds: visible,
+                        border_radius: Default::default(),
+                        border_width: 2.0,
+                        border_color: Color::from_rgb8(255, 215, 121),
+                    },
+                    Color {
+                        a: 0.15,
+                        ..Color::WHITE
+                    },
+                );
+            });
+        }
     }
     fn operate(
         &self,
@@ -351,13 +536,63 @@ where
     }
 }
 
+const MINIMAP_SIZE: f32 = 160.0;
+const MINIMAP_MARGIN: f32 = 10.0;
+
+/// Returns whether a focused [`Viewer`] may handle the key when pressed
+/// without the command modifier: zooming, panning, switching the [`Fit`]
+/// and leaving focus. Arrow keys are only handled while the image overflows
+/// the [`Viewer`] in that direction.
+pub fn handles(key_code: KeyCode) -> bool {
+    matches!(
+        key_code,
+        KeyCode::Plus
+            | KeyCode::NumpadAdd
+            | KeyCode::Equals
+            | KeyCode::Minus
+            | KeyCode::NumpadSubtract
+            | KeyCode::Left
+            | KeyCode::Right
+            | KeyCode::Up
+            | KeyCode::Down
+            | KeyCode::Key0
+            | KeyCode::Numpad0
+            | KeyCode::Key1
+            | KeyCode::Numpad1
+            | KeyCode::Key2
+            | KeyCode::Numpad2
+            | KeyCode::Key3
+            | KeyCode::Numpad3
+            | KeyCode::Key4
+            | KeyCode::Numpad4
+            | KeyCode::Escape
+    )
+}
+
+/// How the image of a [`Viewer`] is fitted into its bounds at scale `1.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Fit {
+    /// The whole image is visible; small images are not enlarged.
+    #[default]
+    Contain,
+    /// The image is as wide as the [`Viewer`].
+    Width,
+    /// The image is as tall as the [`Viewer`].
+    Height,
+    /// The image covers the whole [`Viewer`].
+    Fill,
+}
+
 /// The local state of a [`Viewer`].
 #[derive(Debug, Clone, Copy)]
 pub struct State {
     scale: f32,
+    fit: Fit,
     starting_offset: Vector,
     current_offset: Vector,
     cursor_grabbed_at: Option<Point>,
+    last_click: Option<mouse::Click>,
+    focused: bool,
 }
 
 impl operation::Scrollable for State {
@@ -380,9 +615,12 @@ impl Default for State {
     fn default() -> Self {
         Self {
             scale: 1.0,
+            fit: Fit::default(),
             starting_offset: Vector::default(),
             current_offset: Vector::default(),
             cursor_grabbed_at: None,
+            last_click: None,
+            focused: false,
         }
     }
 }
@@ -394,6 +632,12 @@ impl State {
     }
 
     pub fn snap_to(&mut self, _offset: RelativeOffset) {
+        self.fit_to(Fit::Contain);
+    }
+
+    /// Switches to the given [`Fit`] and drops any zooming and panning.
+    pub fn fit_to(&mut self, fit: Fit) {
+        self.fit = fit;
         self.scale = 1.0;
         self.current_offset = Vector::new(0.0, 0.0);
     }
@@ -448,18 +692,36 @@ where
         let width_ratio = bounds.width / dimensions.0;
         let height_ratio = bounds.height / dimensions.1;
 
-        let ratio = width_ratio.min(height_ratio);
+        let ratio = match state.fit {
+            Fit::Contain => width_ratio.min(height_ratio).min(1.0),
+            Fit::Width => width_ratio,
+            Fit::Height => height_ratio,
+            Fit::Fill => width_ratio.max(height_ratio),
+        };
         let scale = state.scale;
 
-        if ratio < 1.0 {
-            (dimensions.0 * ratio * scale, dimensions.1 * ratio * scale)
-        } else {
-            (dimensions.0 * scale, dimensions.1 * scale)
-        }
+        (dimensions.0 * ratio * scale, dimensions.1 * ratio * scale)
     };
 
     Size::new(width, height)
 }
+
+/// Returns the ratio applied to the image when it is fitted into `bounds`
+/// with [`Fit::Contain`], which is `1.0` for images smaller than `bounds`.
+fn fitting_ratio<Renderer>(
+    renderer: &Renderer,
+    handle: &<Renderer as image::Renderer>::Handle,
+    bounds: Size,
+) -> f32
+where
+    Renderer: image::Renderer,
+{
+    let Size { width, height } = renderer.dimensions(handle);
+
+    (bounds.width / width as f32)
+        .min(bounds.height / height as f32)
+        .min(1.0)
+}
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Id(widget::Id);
 
@@ -1,16 +1,35 @@
 #![allow(dead_code)]
 mod audio;
+mod book;
+mod calendar;
+mod cards;
 mod choosing;
+mod comparing;
 mod configs;
+mod decoding;
+mod drawing;
+mod emojis;
 mod entries;
+mod export;
 mod graduation;
 mod imageviewer;
+mod mosaic;
 mod overlay;
 mod pinpoint;
+mod qr;
 mod quadbutton;
+mod relationgraph;
+mod search;
+mod seating;
 mod sink;
+mod site;
+mod slideshow;
+mod statistics;
 mod subscriptions;
+mod terms;
+mod validation;
 mod visiting;
+mod yearbook;
 
 use crate::overlay::Offset;
 use configs::Configs;
@@ -27,6 +46,8 @@ use std::sync::{Arc, Mutex};
 use toml::value::Table;
 
 pub static DELETE_FILES_ON_EXIT: AtomicBool = AtomicBool::new(false);
+/// 界面和导出的图片共用的字体
+pub const FONT: &[u8] = include_bytes!("./YEFONTFuJiYaTi.ttf");
 pub static SCALE_FACTOR: AtomicU64 = AtomicU64::new(0x3FF0000000000000); // 1.0f64
 
 pub fn store_scale_factor(value: f64) {
@@ -83,8 +104,13 @@ pub enum Memories {
 pub struct State {
     stage: Stage,
     idxtable: Table,
+    /// index.toml 中的学期，载入时读一次
+    terms: Vec<terms::Term>,
     storage: String,
     configs: configs::Configs,
+    search: search::Palette,
+    // 正在显示的二维码
+    qr: Option<qr::Popover>,
 }
 
 #[derive(Clone, Debug)]
@@ -93,6 +119,7 @@ enum Stage {
     ChoosingCharacter(ChoosingState),
     ShowingPlots(VisitingState),
     Graduated(GraduationState),
+    Statistics(statistics::StatisticsState),
 }
 
 #[derive(Clone, Debug, Default)]
@@ -107,11 +134,39 @@ pub struct EntryState {
 pub struct ChoosingState {
     on_character: Option<usize>,
     profiles: Vec<choosing::Profile>,
+    // 没有照片可看的同学
+    no_photos: Vec<usize>,
     avatars: Vec<choosing::Avatar>,
     homepage_offset: scrollable::RelativeOffset,
     description: String,
     previous_stage: Option<EntryState>,
     element_count: usize,
+    // 搜索结果，越靠前越接近
+    ranking: Vec<usize>,
+    // 不为空时以关系图代替头像列表
+    network: Option<relationgraph::Network>,
+    // 不为空时以座位表代替头像列表
+    seating: Option<seating::Chart>,
+    // 不为空时以所有人的表情包代替头像列表
+    emojis: Option<emojis::Browser>,
+    // 只显示这一类人，为空时显示所有人
+    role: Option<choosing::Role>,
+    // 不为空时把 on_character 和另一个人并排对比
+    comparison: Option<comparing::Comparison>,
+    // 上一次导出的结果
+    notice: String,
+    // 选中要一起回忆的同学
+    companions: Vec<usize>,
+    require_all: bool,
+}
+
+impl ChoosingState {
+    /// 搜索结果中没有被身份筛选藏起来的人，顺序与 `ranking` 相同。
+    fn visible(&self) -> impl Iterator<Item = usize> + '_ {
+        self.ranking.iter().copied().filter(|&i| {
+            self.avatars[i].shown && self.role.map_or(true, |role| self.profiles[i].role == role)
+        })
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -120,6 +175,12 @@ pub struct VisitingState {
     on_event: usize,
     events: Arc<Mutex<Vec<visiting::Event>>>,
     homepage_offset: scrollable::RelativeOffset,
+    company: visiting::Company,
+    // 每两人同框的照片数
+    pair_counts: Vec<(usize, usize, usize)>,
+    slideshow: slideshow::Settings,
+    // 上一次导出的结果
+    notice: String,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -128,24 +189,51 @@ pub struct GraduationState {
     show_panel: bool,
     on_image: usize,
     images: Vec<graduation::Panorama>,
+    // 不为空时显示拼好的马赛克
+    mosaic: Option<mosaic::Mosaic>,
 }
 
 #[derive(Clone, Debug)]
 pub enum Message {
+    AdjustBrightness(i32),
+    AdjustContrast(f32),
+    AnimationTick,
+    ApplyAdjustment,
+    ArchiveExported(String),
     BackStage,
+    BuildMosaic(mosaic::Target),
     FontLoaded(Result<(), iced::font::Error>),
     ChoseCharacter(usize),
     ClickedPin(usize),
+    CloseMosaic,
+    CompareWith(usize),
+    CopyEmoji(usize, usize),
     CopyText(String),
     DescriptionEdited(String),
+    EmojiDone(String),
+    EmojiSearched(String),
     EscapeFullScreen,
+    ExportBook,
+    ExportCalendar(Option<usize>),
+    ExportMosaic,
+    ExportSite,
+    ExportSlideshow,
+    ExportStickers(usize),
+    ExportYearbook(usize),
+    Exported(String),
     FetchImage(Result<Memories, Error>),
     FinishedTyping,
+    GraphFiltered(String),
+    GraphMoved(iced::Vector),
+    GraphZoomed(f32, iced::Vector),
+    HideQrCode,
+    HideSearch,
     HideSettings,
     HomepageScrolled(scrollable::Viewport),
     IsDarkTheme(bool),
     Loaded(Result<State, Error>),
     LoadedImage(Result<EntryState, Error>),
+    MakeCard,
     ModifyVolume(f32),
     NextEvent,
     NextPerson,
@@ -153,20 +241,43 @@ pub enum Message {
     NextSong,
     NextStage,
     OpenUrl(Option<String>),
+    OpenProfile(usize),
+    OpenSearch,
     OpenSettings,
+    OpenStatistics,
+    Prefetched,
     PreviousEvent,
     PreviousPerson,
     PreviousPhoto,
+    Redecoded((String, image::Handle)),
     Refresh,
+    ResetAdjustment,
+    RotateImage,
+    SaveEmoji(usize, usize),
     ScaleDown,
     ScaleEnlarge,
     ScaleRestore,
+    SearchEdited(String),
+    SearchIndexed(Vec<search::Entry>),
+    SearchSelected(usize),
+    SeatingChosen(String),
     SelectedImage(String),
+    ShowQrCode(String),
+    SlideshowDuration(slideshow::Duration),
+    SlideshowWidth(slideshow::Width),
+    StopComparing,
     SwitchDeleteFilesStatus,
     SwitchMusicStatus,
+    SwitchRole(Option<choosing::Role>),
+    ToggleCompanion(usize),
+    ToggleEmojis,
+    ToggleGraph,
     ToggleMode,
     TogglePanelShown,
+    ToggleRequireAll(bool),
+    ToggleSeating,
     UnChoose,
+    VisitTogether,
 }
 
 #[derive(Clone, Debug)]
@@ -192,8 +303,7 @@ impl Application for Memories {
         (
             Memories::Initialization,
             Command::batch(vec![
-                iced::font::load(include_bytes!("./YEFONTFuJiYaTi.ttf").as_slice())
-                    .map(Message::FontLoaded),
+                iced::font::load(FONT).map(Message::FontLoaded),
                 Command::perform(State::get_idx(None), Message::Loaded),
             ]),
         )
@@ -222,6 +332,7 @@ impl Application for Memories {
                             [img.on_image]
                     )
                 }
+                Stage::Statistics(_) => "班级统计",
             },
             _ => "加载中",
         };
@@ -348,8 +459,49 @@ impl Application for Memories {
                     }
                     Message::EscapeFullScreen => {
                         state.configs.full_screened = false;
+                        state.search.shown = false;
                         return iced::window::change_mode(state.configs.id, Mode::Windowed);
                     }
+                    Message::OpenSearch => {
+                        state.search.shown = true;
+                        let focus = text_input::focus(search::palette_id());
+                        if !state.search.index.is_empty() {
+                            return focus;
+                        }
+                        // 索引要读遍所有人的资料，第一次打开时才建立
+                        return Command::batch(vec![
+                            focus,
+                            Command::perform(
+                                search::build_index(
+                                    state.storage.clone(),
+                                    state.idxtable.clone(),
+                                    state.terms.clone(),
+                                ),
+                                Message::SearchIndexed,
+                            ),
+                        ]);
+                    }
+                    Message::HideSearch => {
+                        state.search.shown = false;
+                        return Command::none();
+                    }
+                    Message::SearchEdited(query) => {
+                        state.search.query = query;
+                        state.search.search();
+                        return Command::none();
+                    }
+                    Message::SearchIndexed(index) => {
+                        state.search.index = index;
+                        state.search.search();
+                        return Command::none();
+                    }
+                    Message::SearchSelected(selected) => {
+                        let target = state.search.index[selected].target.clone();
+                        state.search.shown = false;
+                        let state = state.to_owned();
+                        *self = Memories::Loading(state.configs.clone());
+                        return Command::perform(search::jump(state, target), Message::Loaded);
+                    }
                     Message::ScaleDown => {
                         store_scale_factor(load_scale_factor() / 1.05);
                         configs::save_configs(state);
@@ -377,6 +529,39 @@ impl Application for Memories {
                         state.configs.shown = true;
                         return Command::none();
                     }
+                    Message::ShowQrCode(link) => {
+                        state.qr = Some(qr::Popover::new(link));
+                        return Command::none();
+                    }
+                    Message::HideQrCode => {
+                        state.qr = None;
+                        return Command::none();
+                    }
+                    Message::ExportBook => {
+                        state.configs.notice = String::from("正在导出...");
+                        return Command::perform(
+                            book::export(state.to_owned()),
+                            Message::ArchiveExported,
+                        );
+                    }
+                    Message::ExportCalendar(None) => {
+                        state.configs.notice = String::from("正在导出...");
+                        return Command::perform(
+                            calendar::export(state.to_owned(), None),
+                            Message::ArchiveExported,
+                        );
+                    }
+                    Message::ExportSite => {
+                        state.configs.notice = String::from("正在导出...");
+                        return Command::perform(
+                            site::export(state.to_owned()),
+                            Message::ArchiveExported,
+                        );
+                    }
+                    Message::ArchiveExported(notice) => {
+                        state.configs.notice = notice;
+                        return Command::none();
+                    }
                     Message::HideSettings => {
                         state.configs.shown = false;
                         configs::save_configs(state);
@@ -461,11 +646,59 @@ impl Application for Memories {
                                         .image_names[vision.on_image]
                                     )
                                 }
+                                Stage::Statistics(_) => format!("{}/events.toml", state.storage),
                             };
                             subscriptions::open_url(name);
                         }
                         return Command::none();
                     }
+                    Message::AdjustBrightness(_)
+                    | Message::AdjustContrast(_)
+                    | Message::ApplyAdjustment
+                    | Message::ResetAdjustment
+                    | Message::RotateImage => {
+                        let Some(path) = current_image_path(state) else {
+                            return Command::none();
+                        };
+                        decoding::modify_adjustment(&path, |adjustment| match &message {
+                            Message::AdjustBrightness(brightness) => {
+                                adjustment.brightness = *brightness
+                            }
+                            Message::AdjustContrast(contrast) => adjustment.contrast = *contrast,
+                            Message::ResetAdjustment => *adjustment = Default::default(),
+                            Message::RotateImage => {
+                                adjustment.rotation = (adjustment.rotation + 90) % 360
+                            }
+                            _ => {}
+                        });
+                        // 拖动滑块的过程中不重新解码，松手后再说
+                        if let Message::AdjustBrightness(_) | Message::AdjustContrast(_) = message {
+                            return Command::none();
+                        }
+                        if let Err(err) = decoding::commit_adjustment(&state.storage, &path) {
+                            let notice = format!("调整没能保存：{}", err);
+                            match state.stage {
+                                Stage::ShowingPlots(ref mut displayer) => displayer.notice = notice,
+                                _ => eprintln!("{}", notice),
+                            }
+                        }
+                        return Command::perform(decoding::redecode(path), Message::Redecoded);
+                    }
+                    Message::Redecoded((path, handle)) => {
+                        if let Stage::ShowingPlots(ref displayer) = state.stage {
+                            let mut events = displayer.events.lock().unwrap();
+                            for event in events.iter_mut() {
+                                for experience in event.experiences.iter_mut() {
+                                    if experience.handle.is_some()
+                                        && format!("{}{}", state.storage, experience.path) == path
+                                    {
+                                        experience.handle = Some(handle.clone());
+                                    }
+                                }
+                            }
+                        }
+                        return Command::none();
+                    }
                     Message::BackStage | Message::NextStage => {
                         configs::save_configs(state);
                         // 这里不可以直接返回！
@@ -516,33 +749,60 @@ impl Application for Memories {
                                     Message::Loaded,
                                 );
                             }
+                            Message::OpenStatistics => {
+                                let state = state.to_owned();
+                                *self = Memories::Loading(state.configs.clone());
+                                return Command::perform(
+                                    statistics::load_statistics(state),
+                                    Message::Loaded,
+                                );
+                            }
+                            Message::Prefetched => return Command::none(),
                             _ => {}
                         }
-                        return imageviewer::reset_scale(imageviewer::entryevents_viewer_id(
-                            chosen.on_event,
-                            chosen.on_image,
-                        ));
+                        let paths = {
+                            let preload = chosen.preload.lock().unwrap();
+                            let len = preload.len();
+                            let mut paths = vec![];
+                            for cur_event in [
+                                (chosen.on_event + len - 1) % len,
+                                chosen.on_event,
+                                (chosen.on_event + 1) % len,
+                            ] {
+                                for handle in &preload[cur_event] {
+                                    if let Some(path) = decoding::path_of(handle) {
+                                        paths.push(path);
+                                    }
+                                }
+                            }
+                            paths
+                        };
+                        return Command::batch(vec![
+                            imageviewer::reset_scale(imageviewer::entryevents_viewer_id(
+                                chosen.on_event,
+                                chosen.on_image,
+                            )),
+                            Command::perform(decoding::prefetch(paths), |_| Message::Prefetched),
+                        ]);
                     }
                     Stage::ChoosingCharacter(ref mut choosing) => {
                         match choosing.on_character {
                             None => match message {
                                 Message::DescriptionEdited(new_description) => {
                                     choosing.description = new_description;
-                                    for avatar in &mut choosing.avatars {
-                                        if avatar.name.contains(choosing.description.as_str()) {
-                                            avatar.shown = true;
-                                        } else {
-                                            avatar.shown = false;
-                                        }
-                                    }
-                                    choosing.avatars[0].shown = false;
+                                    choosing.ranking = choosing::rank_avatars(
+                                        &mut choosing.avatars,
+                                        &choosing.description,
+                                    );
+                                }
+                                Message::SwitchRole(role) => {
+                                    choosing.role = role;
                                 }
                                 Message::FinishedTyping => {
-                                    for (index, avatar) in choosing.avatars.iter().enumerate() {
-                                        if avatar.name.contains(choosing.description.as_str()) {
-                                            choosing.on_character = Some(index);
-                                            return Command::none();
-                                        }
+                                    let best = choosing.visible().next();
+                                    if let Some(best) = best {
+                                        choosing.on_character = Some(best);
+                                        return Command::none();
                                     }
                                 }
                                 Message::ChoseCharacter(chosen) => {
@@ -571,11 +831,125 @@ impl Application for Memories {
                                 Message::HomepageScrolled(new_offset) => {
                                     choosing.homepage_offset = new_offset.relative_offset();
                                 }
+                                Message::ToggleCompanion(person) => {
+                                    match choosing.companions.iter().position(|&p| p == person) {
+                                        Some(index) => {
+                                            choosing.companions.remove(index);
+                                        }
+                                        None => choosing.companions.push(person),
+                                    }
+                                }
+                                Message::ToggleRequireAll(require_all) => {
+                                    choosing.require_all = require_all;
+                                }
+                                Message::VisitTogether => {
+                                    if choosing.companions.len() < 2 {
+                                        return Command::none();
+                                    }
+                                    let state = state.to_owned();
+                                    *self = Memories::Loading(state.configs.clone());
+                                    return Command::perform(
+                                        visiting::get_queue(state),
+                                        Message::Loaded,
+                                    );
+                                }
+                                Message::ToggleEmojis => {
+                                    choosing.network = None;
+                                    choosing.seating = None;
+                                    choosing.emojis = match choosing.emojis {
+                                        Some(_) => None,
+                                        None => Some(Default::default()),
+                                    };
+                                }
+                                Message::EmojiSearched(query) => {
+                                    if let Some(ref mut browser) = choosing.emojis {
+                                        browser.query = query;
+                                    }
+                                }
+                                Message::EmojiDone(notice) => {
+                                    if let Some(ref mut browser) = choosing.emojis {
+                                        browser.notice = notice;
+                                    }
+                                }
+                                Message::CopyEmoji(owner, index) => {
+                                    let (path, _) =
+                                        emojis::emoji_file(&choosing.avatars[owner].emoji[index]);
+                                    return Command::perform(
+                                        emojis::copy_image(path),
+                                        Message::EmojiDone,
+                                    );
+                                }
+                                Message::SaveEmoji(owner, index) => {
+                                    let (path, name) =
+                                        emojis::emoji_file(&choosing.avatars[owner].emoji[index]);
+                                    return Command::perform(
+                                        emojis::save_as(path, name),
+                                        Message::EmojiDone,
+                                    );
+                                }
+                                Message::ExportStickers(owner) => {
+                                    let avatar = &choosing.avatars[owner];
+                                    return Command::perform(
+                                        emojis::export_stickers(
+                                            avatar.name.clone(),
+                                            emojis::sticker_files(avatar),
+                                        ),
+                                        Message::EmojiDone,
+                                    );
+                                }
+                                Message::ToggleSeating => {
+                                    choosing.network = None;
+                                    choosing.emojis = None;
+                                    choosing.seating = match choosing.seating {
+                                        Some(_) => None,
+                                        None => Some(seating::Chart::new(
+                                            &state.idxtable,
+                                            &state.terms,
+                                            choosing.avatars.len(),
+                                        )),
+                                    };
+                                }
+                                Message::SeatingChosen(term) => {
+                                    if let Some(ref mut chart) = choosing.seating {
+                                        chart.select(&term);
+                                    }
+                                }
+                                Message::ToggleGraph => {
+                                    choosing.seating = None;
+                                    choosing.emojis = None;
+                                    choosing.network = match choosing.network {
+                                        Some(_) => None,
+                                        None => Some(relationgraph::Network::new(
+                                            &choosing.profiles,
+                                            choosing.avatars.len(),
+                                        )),
+                                    };
+                                }
+                                Message::GraphFiltered(kind) => {
+                                    if let Some(ref mut network) = choosing.network {
+                                        network.filter = if kind == relationgraph::ALL_KINDS {
+                                            None
+                                        } else {
+                                            Some(kind)
+                                        };
+                                    }
+                                }
+                                Message::GraphMoved(delta) => {
+                                    if let Some(ref mut network) = choosing.network {
+                                        network.pan(delta);
+                                    }
+                                }
+                                Message::GraphZoomed(factor, anchor) => {
+                                    if let Some(ref mut network) = choosing.network {
+                                        network.zoom(factor, anchor);
+                                    }
+                                }
                                 _ => {}
                             },
                             Some(chosen) => match message {
                                 Message::UnChoose => {
                                     choosing.on_character = None;
+                                    choosing.comparison = None;
                                     return scrollable::snap_to(
                                         scrollable::Id::new("HomepageScrollable"),
                                         choosing.homepage_offset,
@@ -600,20 +974,132 @@ impl Application for Memories {
                                 }
                                 Message::ChoseCharacter(chosen) => {
                                     choosing.on_character = Some(chosen);
+                                    choosing.comparison = None;
                                     return scrollable::snap_to(
                                         choosing::generate_scrollable_id(chosen),
                                         scrollable::RelativeOffset::START,
                                     );
                                 }
+                                Message::CompareWith(with) => {
+                                    let state = state.to_owned();
+                                    *self = Memories::Loading(state.configs.clone());
+                                    return Command::perform(
+                                        comparing::compare(state, with),
+                                        Message::Loaded,
+                                    );
+                                }
+                                Message::StopComparing => {
+                                    choosing.comparison = None;
+                                }
+                                Message::MakeCard => {
+                                    choosing.notice = String::from("正在制作...");
+                                    let url_prefix = state.idxtable.get("url_prefix").unwrap();
+                                    return Command::perform(
+                                        cards::make(
+                                            state.storage.clone(),
+                                            url_prefix.as_str().unwrap().to_string(),
+                                            cards::of_profile(choosing, chosen),
+                                        ),
+                                        Message::Exported,
+                                    );
+                                }
+                                Message::ExportCalendar(Some(num)) => {
+                                    choosing.notice = String::from("正在导出...");
+                                    return Command::perform(
+                                        calendar::export(state.to_owned(), Some(num)),
+                                        Message::Exported,
+                                    );
+                                }
+                                Message::ExportYearbook(num) => {
+                                    choosing.notice = String::from("正在导出...");
+                                    return Command::perform(
+                                        yearbook::export(state.to_owned(), num),
+                                        Message::Exported,
+                                    );
+                                }
+                                Message::Exported(notice) => {
+                                    choosing.notice = notice;
+                                }
+                                Message::VisitTogether => {
+                                    let Some(comparison) = &choosing.comparison else {
+                                        return Command::none();
+                                    };
+                                    let with = comparison.with;
+                                    comparing::visit_together(choosing, chosen, with);
+                                    let state = state.to_owned();
+                                    *self = Memories::Loading(state.configs.clone());
+                                    return Command::perform(
+                                        visiting::get_queue(state),
+                                        Message::Loaded,
+                                    );
+                                }
                                 Message::CopyText(text) => {
                                     return iced::clipboard::write(text);
                                 }
+                                Message::AnimationTick => {
+                                    for emoji in &mut choosing.avatars[chosen].emoji {
+                                        if let Some(animation) = &mut emoji.animation {
+                                            animation.advance();
+                                        }
+                                    }
+                                }
                                 _ => {}
                             },
                         }
                         Command::none()
                     }
                     Stage::ShowingPlots(ref mut displayer) => {
+                        if let Message::AnimationTick = message {
+                            let mut events = displayer.events.lock().unwrap();
+                            let event = &mut events[displayer.on_event];
+                            let on_experience = event.on_experience;
+                            if let Some(animation) = &mut event.experiences[on_experience].animation
+                            {
+                                animation.advance();
+                            }
+                            return Command::none();
+                        }
+                        match message {
+                            Message::ExportSlideshow => {
+                                displayer.notice = String::from("正在导出...");
+                                let url_prefix = state.idxtable.get("url_prefix").unwrap();
+                                return Command::perform(
+                                    slideshow::export(
+                                        state.storage.clone(),
+                                        url_prefix.as_str().unwrap().to_string(),
+                                        displayer.character_name.clone(),
+                                        slideshow::slides(&state.terms, displayer),
+                                        displayer.slideshow.clone(),
+                                    ),
+                                    Message::Exported,
+                                );
+                            }
+                            Message::MakeCard => {
+                                displayer.notice = String::from("正在制作...");
+                                let url_prefix = state.idxtable.get("url_prefix").unwrap();
+                                return Command::perform(
+                                    cards::make(
+                                        state.storage.clone(),
+                                        url_prefix.as_str().unwrap().to_string(),
+                                        cards::of_photo(&state.terms, displayer),
+                                    ),
+                                    Message::Exported,
+                                );
+                            }
+                            Message::SlideshowDuration(duration) => {
+                                displayer.slideshow.duration = duration;
+                                return Command::none();
+                            }
+                            Message::Exported(notice) => {
+                                displayer.notice = notice;
+                                return Command::none();
+                            }
+                            Message::SlideshowWidth(width) => {
+                                displayer.slideshow.width = width;
+                                return Command::none();
+                            }
+                            _ => {}
+                        }
                         let mut next_stage = false;
                         let cur_image = {
                             let mut events = displayer.events.lock().unwrap();
@@ -658,7 +1144,19 @@ impl Application for Memories {
                                     Message::Loaded,
                                 );
                             }
-                            Message::PreviousEvent | Message::NextEvent => {
+                            Message::OpenProfile(who) => {
+                                let homepage_offset = displayer.homepage_offset;
+                                let state = state.to_owned();
+                                *self = Memories::Loading(state.configs.clone());
+                                return Command::perform(
+                                    choosing::get_configs(Some(who), homepage_offset, state),
+                                    Message::Loaded,
+                                );
+                            }
+                            Message::PreviousEvent
+                            | Message::NextEvent
+                            | Message::PreviousPhoto
+                            | Message::NextPhoto => {
                                 let need_force_run = {
                                     displayer.events.lock().unwrap()[displayer.on_event]
                                         .get_image_handle()
@@ -715,10 +1213,44 @@ impl Application for Memories {
                                     Message::Loaded,
                                 );
                             }
+                            Message::BuildMosaic(target) => {
+                                let state = state.to_owned();
+                                *self = Memories::Loading(state.configs.clone());
+                                return Command::perform(
+                                    mosaic::build(state, target),
+                                    Message::Loaded,
+                                );
+                            }
+                            Message::CloseMosaic => {
+                                vision.mosaic = None;
+                            }
+                            Message::ExportMosaic => {
+                                if let Some(mosaic) = &mut vision.mosaic {
+                                    mosaic.notice = String::from("正在导出...");
+                                    return Command::perform(
+                                        mosaic::export(
+                                            mosaic.target.label.clone(),
+                                            mosaic.handle.clone(),
+                                        ),
+                                        Message::Exported,
+                                    );
+                                }
+                            }
+                            Message::Exported(notice) => {
+                                if let Some(mosaic) = &mut vision.mosaic {
+                                    mosaic.notice = notice;
+                                }
+                            }
                             _ => {}
                         }
                         Command::none()
                     }
+                    Stage::Statistics(ref statistics) => {
+                        if let Message::BackStage = message {
+                            state.stage = Stage::EntryEvents(statistics.previous_stage.clone());
+                        }
+                        Command::none()
+                    }
                 }
             }
         }
@@ -742,10 +1274,9 @@ impl Application for Memories {
             Memories::Loaded(state) => {
                 let content: Element<Message, iced::Renderer> = match &state.stage {
                     Stage::EntryEvents(chosen) => row![
-                        imageviewer::Viewer::new(
-                            chosen.preload.lock().unwrap()[chosen.on_event][chosen.on_image]
-                                .clone(),
-                        )
+                        imageviewer::Viewer::new(decoding::lookup(
+                            &chosen.preload.lock().unwrap()[chosen.on_event][chosen.on_image]
+                        ))
                         .id(imageviewer::entryevents_viewer_id(
                             chosen.on_event,
                             chosen.on_image
@@ -828,6 +1359,11 @@ impl Application for Memories {
                             )
                             .gap(15)
                             .style(iced::theme::Container::Box),
+                            widget::Button::new(text("班级统计").size(30))
+                                .padding(10)
+                                .style(iced::theme::Button::Secondary)
+                                .on_press(Message::OpenStatistics),
+                            adjustment_panel(&current_image_path(state).unwrap()),
                         ]
                         .spacing(20)
                         .align_items(Alignment::Center)
@@ -838,215 +1374,374 @@ impl Application for Memories {
                     .into(),
                     Stage::ChoosingCharacter(choosing) => match choosing.on_character {
                         None => {
-                            let searchbox = row![
-                                widget::Button::new(text("返回").size(28))
-                                    .style(iced::theme::Button::Secondary)
-                                    .on_press(Message::BackStage)
+                            let back_button = widget::Button::new(text("返回").size(28))
+                                .style(iced::theme::Button::Secondary)
+                                .on_press(Message::BackStage)
+                                .padding(15);
+                            if let Some(network) = &choosing.network {
+                                let toolbar = row![
+                                    back_button,
+                                    widget::Button::new(text("头像列表").size(28))
+                                        .style(iced::theme::Button::Secondary)
+                                        .on_press(Message::ToggleGraph)
+                                        .padding(15),
+                                    widget::pick_list(
+                                        network.filter_options(),
+                                        Some(
+                                            network
+                                                .filter
+                                                .clone()
+                                                .unwrap_or(String::from(relationgraph::ALL_KINDS))
+                                        ),
+                                        Message::GraphFiltered
+                                    )
+                                    .text_size(28)
                                     .padding(15),
-                                text_input("输入以搜索", &choosing.description,)
+                                    text("拖动平移，滚轮缩放，点击头像查看资料").size(20),
+                                ]
+                                .spacing(10)
+                                .align_items(Alignment::Center);
+                                column![
+                                    toolbar,
+                                    relationgraph::Graph::new(network, &choosing.avatars)
+                                ]
+                                .spacing(10)
+                                .into()
+                            } else if let Some(browser) = &choosing.emojis {
+                                emojis::show_emojis(choosing, browser)
+                            } else if let Some(chart) = &choosing.seating {
+                                let toolbar = row![
+                                    back_button,
+                                    widget::Button::new(text("头像列表").size(28))
+                                        .style(iced::theme::Button::Secondary)
+                                        .on_press(Message::ToggleSeating)
+                                        .padding(15),
+                                    widget::pick_list(
+                                        chart.options(),
+                                        chart
+                                            .layouts
+                                            .get(chart.on_layout)
+                                            .map(|layout| layout.term.clone()),
+                                        Message::SeatingChosen
+                                    )
+                                    .text_size(28)
+                                    .padding(15),
+                                    text("点击座位查看资料").size(20),
+                                ]
+                                .spacing(10)
+                                .align_items(Alignment::Center);
+                                column![toolbar, seating::seating_chart(chart, &choosing.avatars)]
+                                    .spacing(10)
+                                    .into()
+                            } else {
+                                let searchbox = row![
+                                    back_button,
+                                    widget::Button::new(text("关系图").size(28))
+                                        .style(iced::theme::Button::Secondary)
+                                        .on_press(Message::ToggleGraph)
+                                        .padding(15),
+                                    widget::Button::new(text("座位表").size(28))
+                                        .style(iced::theme::Button::Secondary)
+                                        .on_press(Message::ToggleSeating)
+                                        .padding(15),
+                                    widget::Button::new(text("表情包").size(28))
+                                        .style(iced::theme::Button::Secondary)
+                                        .on_press(Message::ToggleEmojis)
+                                        .padding(15),
+                                    text_input(
+                                        "输入姓名、拼音或昵称以搜索",
+                                        &choosing.description,
+                                    )
                                     .on_input(Message::DescriptionEdited)
                                     .size(28)
                                     .padding(15)
                                     .on_submit(Message::FinishedTyping),
-                            ];
-                            let mut heads = vec![vec![]];
-                            let mut containing: usize = choosing.element_count;
-                            for (i, avatar) in choosing.avatars.iter().enumerate() {
-                                if !avatar.shown {
-                                    continue;
-                                }
-                                let photo = avatar.photo.to_owned();
-                                let viewer =
-                                    widget::image(photo.clone()).height(Length::Fixed(200.0));
-                                if containing == 0 {
-                                    containing = choosing.element_count;
-                                    heads.push(vec![]);
-                                }
-                                containing -= 1;
-                                heads.last_mut().unwrap().push(
-                                    container(
-                                        widget::Button::new(
+                                ]
+                                .spacing(5);
+                                let mut role_tabs = row![].spacing(5);
+                                for (label, role) in std::iter::once(("全部", None)).chain(
+                                    choosing::Role::ALL
+                                        .iter()
+                                        .map(|role| (role.label(), Some(*role))),
+                                ) {
+                                    role_tabs = role_tabs.push(
+                                        widget::Button::new(text(label).size(24))
+                                            .style(if choosing.role == role {
+                                                iced::theme::Button::Primary
+                                            } else {
+                                                iced::theme::Button::Text
+                                            })
+                                            .padding(10)
+                                            .on_press(Message::SwitchRole(role)),
+                                    );
+                                }
+                                let mut heads = vec![vec![]];
+                                let mut containing: usize = choosing.element_count;
+                                for i in choosing.visible() {
+                                    let avatar = &choosing.avatars[i];
+                                    let photo = avatar.photo.to_owned();
+                                    let viewer =
+                                        widget::image(photo.clone()).height(Length::Fixed(200.0));
+                                    if containing == 0 {
+                                        containing = choosing.element_count;
+                                        heads.push(vec![]);
+                                    }
+                                    containing -= 1;
+                                    heads.last_mut().unwrap().push(
+                                        container(
                                             column![
-                                                viewer,
-                                                text(choosing.avatars[i].name.to_owned()).size(30)
+                                                widget::Button::new(
+                                                    column![
+                                                        viewer,
+                                                        text(choosing.avatars[i].name.to_owned())
+                                                            .size(30)
+                                                    ]
+                                                    .align_items(Alignment::Center),
+                                                )
+                                                .style(iced::theme::Button::Text)
+                                                .padding(10)
+                                                .on_press(Message::ChoseCharacter(i)),
+                                                widget::checkbox(
+                                                    "一起回忆",
+                                                    choosing.companions.contains(&i),
+                                                    move |_| Message::ToggleCompanion(i)
+                                                )
+                                                .text_size(20),
                                             ]
                                             .align_items(Alignment::Center),
                                         )
-                                        .style(iced::theme::Button::Text)
-                                        .padding(10)
-                                        .on_press(Message::ChoseCharacter(i)),
-                                    )
-                                    .width(Length::FillPortion(1))
-                                    .center_x()
-                                    .center_y(),
-                                );
-                            }
-                            let mut scroll_head = column![].align_items(Alignment::Center);
-                            for it in heads {
-                                let mut cur_row = row![].spacing(5);
-                                for j in it {
-                                    cur_row = cur_row.push(j);
+                                        .width(Length::FillPortion(1))
+                                        .center_x()
+                                        .center_y(),
+                                    );
                                 }
-                                scroll_head = scroll_head.push(cur_row);
-                            }
-                            let content = scrollable(column![searchbox, scroll_head,].spacing(10))
+                                let mut scroll_head = column![].align_items(Alignment::Center);
+                                for it in heads {
+                                    let mut cur_row = row![].spacing(5);
+                                    for j in it {
+                                        cur_row = cur_row.push(j);
+                                    }
+                                    scroll_head = scroll_head.push(cur_row);
+                                }
+                                let mut together =
+                                    row![].spacing(15).align_items(Alignment::Center);
+                                if !choosing.companions.is_empty() {
+                                    let names: Vec<String> = choosing
+                                        .companions
+                                        .iter()
+                                        .map(|&person| choosing.avatars[person].name.clone())
+                                        .collect();
+                                    let mut visit = widget::Button::new(text("一起回忆").size(28))
+                                        .style(iced::theme::Button::Primary)
+                                        .padding(15);
+                                    if choosing.companions.len() >= 2 {
+                                        visit = visit.on_press(Message::VisitTogether);
+                                    }
+                                    together = together
+                                        .push(text(format!("已选：{}", names.join("、"))).size(28))
+                                        .push(
+                                            widget::toggler(
+                                                String::from("照片里要有所有人"),
+                                                choosing.require_all,
+                                                Message::ToggleRequireAll,
+                                            )
+                                            .text_size(24)
+                                            .width(Length::Shrink),
+                                        )
+                                        .push(visit);
+                                }
+                                let content = scrollable(
+                                    column![searchbox, role_tabs, together, scroll_head,]
+                                        .spacing(10),
+                                )
                                 .id(scrollable::Id::new("HomepageScrollable"))
                                 .on_scroll(Message::HomepageScrolled);
-                            container(content).width(Length::Fill).into()
+                                container(content).width(Length::Fill).into()
+                            }
                         }
                         Some(chosen) => {
-                            let profile = choosing.profiles[chosen].clone();
-                            let link_color = if state.configs.theme == Theme::Light {
-                                Color::from_rgb8(0, 25, 175)
+                            if let Some(comparison) = &choosing.comparison {
+                                comparing::show_comparison(choosing, chosen, comparison)
                             } else {
-                                Color::from_rgb8(255, 215, 121)
-                            };
-                            let mut content =
-                                column![text(if let Some(name_en) = profile.name_en {
+                                let profile = choosing.profiles[chosen].clone();
+                                let link_color = if state.configs.theme == Theme::Light {
+                                    Color::from_rgb8(0, 25, 175)
+                                } else {
+                                    Color::from_rgb8(255, 215, 121)
+                                };
+                                let mut title = if let Some(name_en) = profile.name_en {
                                     format!("{} ({})", choosing.avatars[chosen].name, name_en)
                                 } else {
                                     choosing.avatars[chosen].name.clone()
-                                })
-                                .size(50)];
-                            content = content.push(show_profiles(profile.nickname, "ta 的昵称"));
-                            if let Some(relations) = profile.relationship {
-                                let mut lists = column![];
-                                for relation in &relations {
-                                    let cur_relation = relation.as_table().unwrap();
-                                    let author =
-                                        cur_relation.get("by").unwrap().as_integer().unwrap()
-                                            as usize;
-                                    lists = lists.push(row![
-                                        text("是 ").size(30),
-                                        widget::Button::new(
-                                            text(choosing.avatars[author].name.clone())
-                                                .size(30)
-                                                .style(link_color)
-                                        )
-                                        .padding(0)
-                                        .on_press(Message::ChoseCharacter(author))
-                                        .style(iced::theme::Button::Text),
-                                        text(format!(
-                                            " 的 {}；",
-                                            cur_relation.get("is").unwrap().as_str().unwrap()
-                                        ))
-                                        .size(30)
-                                    ]);
+                                };
+                                if profile.role.is_teacher() {
+                                    title = format!("{} · {}", title, profile.role.label());
                                 }
-                                if !relations.is_empty() {
+                                let mut content = column![text(title).size(50)];
+                                content =
+                                    content.push(show_profiles(&profile.nickname, "ta 的昵称"));
+                                if !profile.teaching.is_empty() {
+                                    let mut lists = column![];
+                                    for teaching in &profile.teaching {
+                                        lists = lists.push(
+                                            text(if teaching.terms.is_empty() {
+                                                teaching.subject.clone()
+                                            } else {
+                                                format!(
+                                                    "{}：{}",
+                                                    teaching.subject,
+                                                    teaching.terms.join("、")
+                                                )
+                                            })
+                                            .size(30),
+                                        );
+                                    }
                                     content = content.push(column![
-                                        text("ta 的身份").size(50),
+                                        text("任教").size(50),
                                         row![horizontal_space(Length::Fixed(20.0)), lists,]
                                     ]);
                                 }
-                            }
-
-                            let mut emojis = row![].align_items(Alignment::Center).spacing(5);
-                            for (j, i) in choosing.avatars[chosen].emoji.iter().enumerate() {
-                                emojis = emojis.push(
-                                    column![
-                                        imageviewer::Viewer::new(i.emoji.clone())
-                                            .height(Length::Fixed(400.0))
-                                            .id(imageviewer::emoji_id(chosen, j)),
-                                        text(i.emoji_name.clone()).size(30),
-                                        vertical_space(Length::Fixed(5.0))
-                                    ]
-                                    .align_items(Alignment::Center),
-                                );
-                            }
-                            let mut content = column![row![
-                                content,
-                                scrollable(emojis).direction(scrollable::Direction::Horizontal(
-                                    scrollable::Properties::new()
-                                ))
-                            ]];
-                            content = content.push(show_profiles(profile.plots, "ta 的小日常"));
-                            if let Some(summary) = profile.anecdote {
-                                content = content.push(column![
-                                    text("关于 ta").size(50),
-                                    row![
-                                        horizontal_space(Length::Fixed(20.0)),
-                                        column![
-                                            text(format!(
-                                                "兴趣爱好：{}",
-                                                summary.get("interests").unwrap().as_str().unwrap()
-                                            ))
-                                            .size(32)
-                                            .style(Color::from_rgb8(240, 134, 80)),
-                                            text(format!(
-                                                "最想做的事：{}",
-                                                summary.get("want").unwrap().as_str().unwrap()
-                                            ))
-                                            .size(32)
-                                            .style(Color::from_rgb8(240, 135, 132)),
-                                            text(format!(
-                                                "最尴尬的事：{}",
-                                                summary
-                                                    .get("embarrassment")
-                                                    .unwrap()
-                                                    .as_str()
-                                                    .unwrap()
-                                            ))
-                                            .size(32)
-                                            .style(Color::from_rgb8(127, 130, 187))
-                                        ]
-                                    ],
-                                ])
-                            }
-                            if let Some(intro) = profile.introduction {
-                                content = content.push(column![
-                                    text("ta 的自传").size(50),
-                                    row![
-                                        horizontal_space(Length::Fixed(20.0)),
-                                        text(intro).size(30)
-                                    ],
-                                ])
-                            }
-                            if let Some(articles) = profile.article {
-                                let mut lists = column![];
-                                let mut article_vec = vec![];
-
-                                for article in &articles {
-                                    let cur_article = article.as_table().unwrap();
-                                    let content =
-                                        cur_article.get("content").unwrap().as_str().unwrap();
-                                    let date =
-                                        cur_article.get("date").unwrap().as_datetime().unwrap();
-                                    let link = cur_article.get("link").unwrap().as_str().unwrap();
-                                    article_vec.push((date, content, link));
-                                }
-                                article_vec.sort_unstable();
-                                for i in article_vec {
-                                    lists = lists.push(
-                                        row![
-                                            text(i.0)
-                                                .size(20)
-                                                .style(Color::from_rgb8(85, 143, 128)),
+                                if !profile.relationship.is_empty() {
+                                    let mut lists = column![];
+                                    for relation in &profile.relationship {
+                                        let author = relation.by;
+                                        lists = lists.push(row![
+                                            text("是 ").size(30),
                                             widget::Button::new(
-                                                text(i.1).size(30).style(link_color)
+                                                text(choosing.avatars[author].name.clone())
+                                                    .size(30)
+                                                    .style(link_color)
                                             )
                                             .padding(0)
-                                            .on_press(Message::OpenUrl(Some(i.2.to_string())))
+                                            .on_press(Message::ChoseCharacter(author))
                                             .style(iced::theme::Button::Text),
-                                            widget::tooltip(
-                                                widget::button(widget::Svg::new(
-                                                    widget::svg::Handle::from_memory(
-                                                        include_bytes!("./runtime/clipboard.svg")
-                                                            .to_vec()
-                                                    )
-                                                ))
-                                                .height(Length::Fixed(32.0))
-                                                .width(Length::Fixed(50.0))
-                                                .style(iced::theme::Button::Text)
-                                                .on_press(Message::CopyText(i.2.to_string())),
-                                                "复制链接",
-                                                widget::tooltip::Position::Right
-                                            )
+                                            text(format!(" 的 {}；", relation.is)).size(30)
+                                        ]);
+                                    }
+                                    content = content.push(column![
+                                        text("ta 的身份").size(50),
+                                        row![horizontal_space(Length::Fixed(20.0)), lists,]
+                                    ]);
+                                }
+
+                                let mut emojis = row![].align_items(Alignment::Center).spacing(5);
+                                for (j, i) in choosing.avatars[chosen].emoji.iter().enumerate() {
+                                    emojis = emojis.push(
+                                        column![
+                                            imageviewer::Viewer::new(i.current_frame())
+                                                .height(Length::Fixed(400.0))
+                                                .id(imageviewer::emoji_id(chosen, j)),
+                                            text(i.emoji_name.clone()).size(30),
+                                            vertical_space(Length::Fixed(5.0))
                                         ]
-                                        .align_items(Alignment::Start),
+                                        .align_items(Alignment::Center),
                                     );
                                 }
-                                if !articles.is_empty() {
+                                let mut content = column![row![
+                                    content,
+                                    scrollable(emojis).direction(
+                                        scrollable::Direction::Horizontal(
+                                            scrollable::Properties::new()
+                                        )
+                                    )
+                                ]];
+                                content =
+                                    content.push(show_profiles(&profile.plots, "ta 的小日常"));
+                                if let Some(summary) = profile.anecdote {
+                                    let mut answers = column![];
+                                    for (question, answer, color) in [
+                                        (
+                                            "兴趣爱好",
+                                            summary.interests,
+                                            Color::from_rgb8(240, 134, 80),
+                                        ),
+                                        (
+                                            "最想做的事",
+                                            summary.want,
+                                            Color::from_rgb8(240, 135, 132),
+                                        ),
+                                        (
+                                            "最尴尬的事",
+                                            summary.embarrassment,
+                                            Color::from_rgb8(127, 130, 187),
+                                        ),
+                                    ] {
+                                        if let Some(answer) = answer {
+                                            answers = answers.push(
+                                                text(format!("{}：{}", question, answer))
+                                                    .size(32)
+                                                    .style(color),
+                                            );
+                                        }
+                                    }
+                                    content = content.push(column![
+                                        text("关于 ta").size(50),
+                                        row![horizontal_space(Length::Fixed(20.0)), answers],
+                                    ])
+                                }
+                                if let Some(intro) = profile.introduction {
+                                    content = content.push(column![
+                                        text("ta 的自传").size(50),
+                                        row![
+                                            horizontal_space(Length::Fixed(20.0)),
+                                            text(intro).size(30)
+                                        ],
+                                    ])
+                                }
+                                if !profile.article.is_empty() {
+                                    let mut lists = column![];
+                                    let mut article_vec = vec![];
+
+                                    for article in &profile.article {
+                                        let date = article
+                                            .date
+                                            .map(|date| date.to_string())
+                                            .unwrap_or_default();
+                                        article_vec.push((date, &article.content, &article.link));
+                                    }
+                                    article_vec.sort_unstable();
+                                    for i in article_vec {
+                                        lists = lists.push(
+                                            row![
+                                                text(i.0)
+                                                    .size(20)
+                                                    .style(Color::from_rgb8(85, 143, 128)),
+                                                widget::Button::new(
+                                                    text(i.1).size(30).style(link_color)
+                                                )
+                                                .padding(0)
+                                                .on_press(Message::OpenUrl(Some(i.2.to_string())))
+                                                .style(iced::theme::Button::Text),
+                                                widget::tooltip(
+                                                    widget::button(widget::Svg::new(
+                                                        widget::svg::Handle::from_memory(
+                                                            include_bytes!(
+                                                                "./runtime/clipboard.svg"
+                                                            )
+                                                            .to_vec()
+                                                        )
+                                                    ))
+                                                    .height(Length::Fixed(32.0))
+                                                    .width(Length::Fixed(50.0))
+                                                    .style(iced::theme::Button::Text)
+                                                    .on_press(Message::CopyText(i.2.to_string())),
+                                                    "复制链接",
+                                                    widget::tooltip::Position::Right
+                                                ),
+                                                widget::tooltip(
+                                                    widget::Button::new(text("二维码").size(20))
+                                                        .padding(5)
+                                                        .style(iced::theme::Button::Text)
+                                                        .on_press(Message::ShowQrCode(
+                                                            i.2.to_string()
+                                                        )),
+                                                    "用手机扫码阅读",
+                                                    widget::tooltip::Position::Right
+                                                )
+                                            ]
+                                            .align_items(Alignment::Start),
+                                        );
+                                    }
                                     content =
                                         content.push(widget::vertical_space(Length::Fixed(10.0)));
                                     content = content.push(row![
@@ -1058,123 +1753,184 @@ impl Application for Memories {
                                         lists
                                     ]);
                                 }
-                            }
-                            if let Some(reviews) = profile.reviews {
-                                let mut lists = column![].spacing(15);
-                                for (index, review) in reviews.iter().enumerate() {
-                                    let cur_review = review.as_str().unwrap();
-                                    lists = lists.push(column![
-                                        text(choosing::SEMESTER_NAMES[index])
+                                if !profile.reviews.is_empty() {
+                                    let mut lists = column![].spacing(15);
+                                    for review in &profile.reviews {
+                                        let mut heading = row![text(review.term.clone())
                                             .size(25)
-                                            .style(Color::from_rgb8(120, 158, 204)),
-                                        row![
-                                            horizontal_space(Length::Fixed(20.0)),
-                                            text(cur_review).size(30)
-                                        ]
-                                    ]);
-                                }
-                                content = content.push(column![
-                                    text("班主任评语").size(50),
-                                    row![horizontal_space(Length::Fixed(20.0)), lists,]
-                                ]);
-                            }
-                            if let Some(comments) = profile.comment {
-                                let mut lists = column![].spacing(15);
-                                for comment in &comments {
-                                    let cur_comment = comment.as_table().unwrap();
-                                    let with =
-                                        cur_comment.get("from").unwrap().as_integer().unwrap()
-                                            as usize;
-                                    lists = lists.push(column![
-                                        row![
-                                            text("来自 ").size(40),
-                                            widget::Button::new(
-                                                text(choosing.avatars[with].name.clone()).size(40).style(link_color)
-                                            )
-                                            .padding(0)
-                                            .style(iced::theme::Button::Text)
-                                            .on_press(Message::ChoseCharacter(with)),
-                                            text("：").size(40)
-                                        ],
-                                        row![
-                                            widget::Svg::new(widget::svg::Handle::from_memory(
-                                                include_bytes!("./runtime/quote-left.svg").to_vec()
-                                            ))
-                                            .width(Length::Fixed(30.0)),
-                                            column![text(
-                                                cur_comment
-                                                    .get("description")
-                                                    .unwrap()
-                                                    .as_str()
-                                                    .expect(
-                                                        "Cannot convert `description` into String"
+                                            .style(Color::from_rgb8(120, 158, 204))]
+                                        .align_items(Alignment::Center);
+                                        if let Some(teacher) =
+                                            choosing::head_teacher(&choosing.profiles, &review.term)
+                                        {
+                                            heading =
+                                                heading.push(text(" · 班主任 ").size(25)).push(
+                                                    widget::Button::new(
+                                                        text(
+                                                            choosing.avatars[teacher].name.clone(),
+                                                        )
+                                                        .size(25)
+                                                        .style(link_color),
                                                     )
-                                            )
-                                            .size(30),
-                                            text(format!(
-                                                "于 {} ",
-                                                cur_comment.get("date").unwrap().as_datetime().unwrap()
-                                            ))
-                                            .size(30)]
-                                            .align_items(Alignment::End)
-                                        ]
+                                                    .padding(0)
+                                                    .on_press(Message::ChoseCharacter(teacher))
+                                                    .style(iced::theme::Button::Text),
+                                                );
+                                        }
+                                        lists = lists.push(column![
+                                            heading,
+                                            row![
+                                                horizontal_space(Length::Fixed(20.0)),
+                                                text(review.content.clone()).size(30)
+                                            ]
+                                        ]);
+                                    }
+                                    content = content.push(column![
+                                        text("班主任评语").size(50),
+                                        row![horizontal_space(Length::Fixed(20.0)), lists,]
                                     ]);
                                 }
-                                if !comments.is_empty() {
+                                if !profile.comment.is_empty() {
+                                    let mut lists = column![].spacing(15);
+                                    for comment in &profile.comment {
+                                        let with = comment.from;
+                                        let date = comment
+                                            .date
+                                            .map(|date| format!("于 {} ", date))
+                                            .unwrap_or_default();
+                                        lists = lists.push(column![
+                                            row![
+                                                text("来自 ").size(40),
+                                                widget::Button::new(
+                                                    text(choosing.avatars[with].name.clone())
+                                                        .size(40)
+                                                        .style(link_color)
+                                                )
+                                                .padding(0)
+                                                .style(iced::theme::Button::Text)
+                                                .on_press(Message::ChoseCharacter(with)),
+                                                text("：").size(40)
+                                            ],
+                                            row![
+                                                widget::Svg::new(widget::svg::Handle::from_memory(
+                                                    include_bytes!("./runtime/quote-left.svg")
+                                                        .to_vec()
+                                                ))
+                                                .width(Length::Fixed(30.0)),
+                                                column![
+                                                    text(comment.description.clone()).size(30),
+                                                    text(date).size(30)
+                                                ]
+                                                .align_items(Alignment::End)
+                                            ]
+                                        ]);
+                                    }
                                     content = content.push(column![
                                         text("大家对 ta 的评价").size(50),
                                         row![horizontal_space(Length::Fixed(20.0)), lists,]
                                     ]);
                                 }
-                            }
-                            let mut apply_button = row![widget::Button::new(text("返回").size(30))
-                                .padding(15)
-                                .style(iced::theme::Button::Secondary)
-                                .on_press(Message::UnChoose),]
-                            .spacing(20);
-                            if !choosing::CHARACTERS_WITH_NO_PHOTOS.contains(&chosen) {
-                                apply_button = apply_button.push(
-                                    widget::Button::new(text("选好啦").size(30))
+                                let mut apply_button =
+                                    row![widget::Button::new(text("返回").size(30))
                                         .padding(15)
-                                        .style(iced::theme::Button::Primary)
-                                        .on_press(Message::NextStage),
-                                );
+                                        .style(iced::theme::Button::Secondary)
+                                        .on_press(Message::UnChoose),]
+                                    .spacing(20)
+                                    .align_items(Alignment::Center)
+                                    .push(comparing::pick(choosing, chosen))
+                                    .push(
+                                        widget::Button::new(text("导出同学录页面").size(30))
+                                            .padding(15)
+                                            .style(iced::theme::Button::Secondary)
+                                            .on_press(Message::ExportYearbook(chosen)),
+                                    )
+                                    .push(
+                                        widget::Button::new(text("制作分享卡片").size(30))
+                                            .padding(15)
+                                            .style(iced::theme::Button::Secondary)
+                                            .on_press(Message::MakeCard),
+                                    )
+                                    .push(
+                                        widget::Button::new(text("导出日历").size(30))
+                                            .padding(15)
+                                            .style(iced::theme::Button::Secondary)
+                                            .on_press(Message::ExportCalendar(Some(chosen))),
+                                    );
+                                if !choosing.no_photos.contains(&chosen) {
+                                    apply_button = apply_button.push(
+                                        widget::Button::new(text("选好啦").size(30))
+                                            .padding(15)
+                                            .style(iced::theme::Button::Primary)
+                                            .on_press(Message::NextStage),
+                                    );
+                                }
+                                apply_button = apply_button.push(text(&choosing.notice).size(20));
+                                container(
+                                    scrollable(row![
+                                        horizontal_space(Length::FillPortion(1)),
+                                        column![content.spacing(5), apply_button]
+                                            .align_items(Alignment::Center)
+                                            .width(Length::FillPortion(18)),
+                                        horizontal_space(Length::FillPortion(1)),
+                                    ])
+                                    .id(choosing::generate_scrollable_id(chosen)),
+                                )
+                                .center_x()
+                                .center_y()
+                                .width(Length::Fill)
+                                .height(Length::Fill)
+                                .into()
                             }
-                            container(
-                                scrollable(row![
-                                    horizontal_space(Length::FillPortion(1)),
-                                    column![content.spacing(5), apply_button]
-                                        .align_items(Alignment::Center)
-                                        .width(Length::FillPortion(18)),
-                                    horizontal_space(Length::FillPortion(1)),
-                                ])
-                                .id(choosing::generate_scrollable_id(chosen)),
-                            )
-                            .center_x()
-                            .center_y()
-                            .width(Length::Fill)
-                            .height(Length::Fill)
-                            .into()
                         }
                     },
                     Stage::ShowingPlots(displayer) => {
                         let events = displayer.events.lock().unwrap();
                         let experiences = &events[displayer.on_event].experiences;
                         let cur_img = &experiences[events[displayer.on_event].on_experience];
-                        row![
-                            imageviewer::Viewer::new(cur_img.handle.clone().unwrap())
+                        let displaying: Element<Message, iced::Renderer> = if let Some(handle) =
+                            match &cur_img.animation {
+                                Some(animation) => Some(animation.current_frame()),
+                                None => cur_img.handle.clone(),
+                            } {
+                            imageviewer::Viewer::new(handle)
                                 .id(imageviewer::showingplots_viewer_id(
                                     displayer.on_event,
-                                    events[displayer.on_event].on_experience
+                                    events[displayer.on_event].on_experience,
+                                ))
+                                .regions(tagged_regions(
+                                    &format!("{}{}", state.storage, cur_img.path),
+                                    cur_img,
                                 ))
                                 .width(Length::FillPortion(4))
-                                .height(Length::Fill),
+                                .height(Length::Fill)
+                                .into()
+                        } else {
+                            let status = match &cur_img.failed {
+                                Some(err) => format!("图片加载失败：{}", err),
+                                None => String::from("图片加载中..."),
+                            };
+                            container(text(status).size(40))
+                                .width(Length::FillPortion(4))
+                                .height(Length::Fill)
+                                .center_x()
+                                .center_y()
+                                .into()
+                        };
+                        row![
+                            displaying,
                             column![
                                 button_from_svg(include_bytes!("./runtime/gears.svg"))
                                     .width(Length::Fixed(80.0))
                                     .on_press(Message::OpenSettings),
                                 text(events[displayer.on_event].description.clone()).size(50),
-                                text(format!("拍摄于 {}", cur_img.shot)).size(30),
+                                text(match &cur_img.shot {
+                                    Some(shot) =>
+                                        format!("拍摄于 {}", terms::caption(&state.terms, shot)),
+                                    None => String::from("拍摄时间不详"),
+                                })
+                                .size(30),
+                                company_panel(state, displayer, cur_img),
+                                people_panel(state, cur_img),
                                 row![
                                     if displayer.on_event > 0 {
                                         Element::from(
@@ -1218,6 +1974,20 @@ impl Application for Memories {
                                 )
                                 .gap(8)
                                 .style(iced::theme::Container::Box),
+                                adjustment_panel(&format!("{}{}", state.storage, cur_img.path)),
+                                slideshow::panel(&displayer.slideshow),
+                                widget::Button::new(text("制作分享卡片").size(30))
+                                    .padding(10)
+                                    .style(iced::theme::Button::Secondary)
+                                    .on_press(Message::MakeCard),
+                                widget::Button::new(text("扫码查看照片").size(30))
+                                    .padding(10)
+                                    .style(iced::theme::Button::Secondary)
+                                    .on_press(Message::ShowQrCode(qr::photo_link(
+                                        &state.idxtable,
+                                        &cur_img.path
+                                    ))),
+                                text(&displayer.notice).size(18),
                                 row![
                                     widget::tooltip(
                                         button_from_svg(include_bytes!(
@@ -1250,112 +2020,125 @@ impl Application for Memories {
                         .into()
                     }
                     Stage::Graduated(vision) => {
-                        let images =
-                            &vision.images[graduation::ON_LOCATION.load(Ordering::Relaxed)];
-                        let displayer =
-                            imageviewer::Viewer::new(images.image[vision.on_image].clone())
-                                .id(imageviewer::graduation_viewer_id(
-                                    graduation::ON_LOCATION.load(Ordering::Relaxed),
-                                    vision.on_image,
-                                ))
-                                .width(Length::Fill)
-                                .height(Length::Fill);
-                        if vision.show_panel {
-                            let mut current = vec![];
-                            let mut offsets = vec![];
-                            for pan in &vision.images {
-                                let pinpoint = |index| {
-                                    Element::from(
-                                        crate::button_from_svg(
-                                            if index
-                                                == graduation::ON_LOCATION.load(Ordering::Relaxed)
-                                            {
-                                                include_bytes!("./runtime/location-check.svg")
-                                            } else {
-                                                include_bytes!("./runtime/location-pin.svg")
-                                            },
+                        if let Some(mosaic) = &vision.mosaic {
+                            mosaic::show_mosaic(mosaic)
+                        } else {
+                            let images =
+                                &vision.images[graduation::ON_LOCATION.load(Ordering::Relaxed)];
+                            let displayer =
+                                imageviewer::Viewer::new(images.image[vision.on_image].clone())
+                                    .id(imageviewer::graduation_viewer_id(
+                                        graduation::ON_LOCATION.load(Ordering::Relaxed),
+                                        vision.on_image,
+                                    ))
+                                    .width(Length::Fill)
+                                    .height(Length::Fill);
+                            if vision.show_panel {
+                                let mut current = vec![];
+                                let mut offsets = vec![];
+                                for pan in &vision.images {
+                                    let pinpoint = |index| {
+                                        Element::from(
+                                            crate::button_from_svg(
+                                                if index
+                                                    == graduation::ON_LOCATION
+                                                        .load(Ordering::Relaxed)
+                                                {
+                                                    include_bytes!("./runtime/location-check.svg")
+                                                } else {
+                                                    include_bytes!("./runtime/location-pin.svg")
+                                                },
+                                            )
+                                            .width(Length::Fixed(36.0))
+                                            .on_press(Message::ClickedPin(index)),
                                         )
-                                        .width(Length::Fixed(36.0))
-                                        .on_press(Message::ClickedPin(index)),
+                                    };
+                                    current.push(pinpoint);
+                                    offsets.push(Offset {
+                                        x: pan.pinpoint.0,
+                                        y: pan.pinpoint.1,
+                                    });
+                                }
+                                let map = container(widget::image(image::Handle::from_memory(
+                                    include_bytes!("./runtime/map.jpg"),
+                                )));
+                                let pinpointed_map =
+                                    crate::pinpoint::Pinpoint::new(map, current, offsets);
+                                let mut components = column![
+                                    widget::tooltip(
+                                        crate::button_from_svg(include_bytes!(
+                                            "./runtime/backward-step.svg"
+                                        ))
+                                        .width(Length::Fixed(40.0))
+                                        .on_press(Message::BackStage),
+                                        "返回",
+                                        widget::tooltip::Position::Top,
                                     )
-                                };
-                                current.push(pinpoint);
-                                offsets.push(Offset {
-                                    x: pan.pinpoint.0,
-                                    y: pan.pinpoint.1,
-                                });
-                            }
-                            let map = container(widget::image(image::Handle::from_memory(
-                                include_bytes!("./runtime/map.jpg"),
-                            )));
-                            let pinpointed_map =
-                                crate::pinpoint::Pinpoint::new(map, current, offsets);
-                            let mut components = column![
-                                widget::tooltip(
-                                    crate::button_from_svg(include_bytes!(
-                                        "./runtime/backward-step.svg"
-                                    ))
-                                    .width(Length::Fixed(40.0))
-                                    .on_press(Message::BackStage),
-                                    "返回",
-                                    widget::tooltip::Position::Top,
-                                )
-                                .style(iced::theme::Container::Box),
-                                widget::tooltip(
-                                    crate::button_from_svg(include_bytes!(
-                                        "./runtime/chevron-up.svg"
-                                    ))
-                                    .width(Length::Fixed(60.0))
-                                    .on_press(Message::TogglePanelShown),
-                                    "全屏查看",
-                                    widget::tooltip::Position::Top,
-                                )
-                                .gap(-5.0)
-                                .style(iced::theme::Container::Box)
-                            ]
-                            .align_items(Alignment::Center);
-                            if images.image.len() > 1 {
-                                components = components.push(widget::pick_list(
-                                    images.image_names.clone(),
-                                    Some(images.image_names[vision.on_image].clone()),
-                                    Message::SelectedImage,
-                                ));
-                            }
-                            column![
-                                pinpointed_map,
-                                row![
-                                    container(components).center_y().height(Length::Fill),
-                                    displayer
-                                ]
-                            ]
-                            .into()
-                        } else {
-                            column![overlay::Component::new(displayer, || {
-                                column![
-                                    vertical_space(Length::Fixed(40.0)),
+                                    .style(iced::theme::Container::Box),
                                     widget::tooltip(
                                         crate::button_from_svg(include_bytes!(
-                                            "./runtime/chevron-down.svg"
+                                            "./runtime/chevron-up.svg"
                                         ))
                                         .width(Length::Fixed(60.0))
                                         .on_press(Message::TogglePanelShown),
-                                        "收起",
+                                        "全屏查看",
                                         widget::tooltip::Position::Top,
                                     )
-                                    .gap(-10.0)
-                                    .style(iced::theme::Container::Box),
+                                    .gap(-5.0)
+                                    .style(iced::theme::Container::Box)
+                                ]
+                                .align_items(Alignment::Center);
+                                components = components.push(mosaic::pick(&state.idxtable));
+                                if images.image.len() > 1 {
+                                    components = components.push(widget::pick_list(
+                                        images.image_names.clone(),
+                                        Some(images.image_names[vision.on_image].clone()),
+                                        Message::SelectedImage,
+                                    ));
+                                }
+                                column![
+                                    pinpointed_map,
+                                    row![
+                                        container(components).center_y().height(Length::Fill),
+                                        displayer
+                                    ]
                                 ]
                                 .into()
-                            })]
-                            .into()
+                            } else {
+                                column![overlay::Component::new(displayer, || {
+                                    column![
+                                        vertical_space(Length::Fixed(40.0)),
+                                        widget::tooltip(
+                                            crate::button_from_svg(include_bytes!(
+                                                "./runtime/chevron-down.svg"
+                                            ))
+                                            .width(Length::Fixed(60.0))
+                                            .on_press(Message::TogglePanelShown),
+                                            "收起",
+                                            widget::tooltip::Position::Top,
+                                        )
+                                        .gap(-10.0)
+                                        .style(iced::theme::Container::Box),
+                                    ]
+                                    .into()
+                                })]
+                                .into()
+                            }
                         }
                     }
+                    Stage::Statistics(statistics) => statistics::show_statistics(state, statistics),
                 };
-                if state.configs.shown {
+                let content = if state.configs.shown {
                     configs::settings_over(state.configs.clone(), content)
                 } else {
                     content
-                }
+                };
+                let content = if state.search.shown {
+                    search::palette_over(&state.search, content)
+                } else {
+                    content
+                };
+                qr::qr_over(state.qr.as_ref(), content)
             }
         }
     }
@@ -1363,14 +2146,30 @@ impl Application for Memories {
         match self {
             Memories::Initialization => iced::Subscription::none(),
             Memories::Loading(_) => iced::event::listen_with(subscriptions::on_loading),
-            Memories::Loaded(state) => match state.stage {
-                Stage::EntryEvents(_) => iced::event::listen_with(subscriptions::on_entry_state),
-                Stage::ChoosingCharacter(_) => {
-                    iced::event::listen_with(subscriptions::on_choosing_character)
+            Memories::Loaded(state) => {
+                let listening = match state.stage {
+                    _ if state.search.shown => iced::event::listen_with(subscriptions::on_search),
+                    Stage::EntryEvents(_) => {
+                        iced::event::listen_with(subscriptions::on_entry_state)
+                    }
+                    Stage::ChoosingCharacter(_) => {
+                        iced::event::listen_with(subscriptions::on_choosing_character)
+                    }
+                    Stage::ShowingPlots(_) => {
+                        iced::event::listen_with(subscriptions::on_showing_plots)
+                    }
+                    Stage::Graduated(_) => iced::event::listen_with(subscriptions::on_graduation),
+                    Stage::Statistics(_) => iced::event::listen_with(subscriptions::on_statistics),
+                };
+                if is_animating(state) {
+                    iced::Subscription::batch(vec![
+                        listening,
+                        iced::time::every(decoding::ANIMATION_TICK).map(|_| Message::AnimationTick),
+                    ])
+                } else {
+                    listening
                 }
-                Stage::ShowingPlots(_) => iced::event::listen_with(subscriptions::on_showing_plots),
-                Stage::Graduated(_) => iced::event::listen_with(subscriptions::on_graduation),
-            },
+            }
         }
     }
     fn scale_factor(&self) -> f64 {
@@ -1386,23 +2185,213 @@ impl Application for Memories {
     }
 }
 
+/// 只有屏幕上正显示着动图时才需要定时刷新，看不见的动图就停在原地。
+fn is_animating(state: &State) -> bool {
+    match &state.stage {
+        Stage::ChoosingCharacter(choosing) => match choosing.on_character {
+            Some(chosen) => choosing.avatars[chosen]
+                .emoji
+                .iter()
+                .any(|emoji| emoji.animation.is_some()),
+            None => false,
+        },
+        Stage::ShowingPlots(displayer) => {
+            let events = displayer.events.lock().unwrap();
+            let event = &events[displayer.on_event];
+            event.experiences[event.on_experience].animation.is_some()
+        }
+        _ => false,
+    }
+}
+
 pub fn button_from_svg(position: &'static [u8]) -> widget::Button<'static, Message> {
     widget::Button::new(widget::Svg::new(widget::svg::Handle::from_memory(position)))
         .style(iced::theme::Button::Text)
 }
 
-fn show_profiles(item: Option<toml::value::Array>, with_name: &str) -> Element<Message> {
-    if let Some(item) = item {
-        let mut lists = column![];
-        for i in &item {
-            lists = lists.push(text(i.as_str().unwrap().to_string()).size(30));
+/// 当前显示的照片在本地的路径，只有能调整的照片才有
+fn current_image_path(state: &State) -> Option<String> {
+    match &state.stage {
+        Stage::EntryEvents(chosen) => Some(format!(
+            "{}{}",
+            state.storage,
+            state
+                .get_current_event(chosen.on_event)
+                .get("image")
+                .unwrap()
+                .as_array()
+                .unwrap()[chosen.on_image]
+                .as_str()
+                .unwrap()
+        )),
+        Stage::ShowingPlots(displayer) => {
+            let events = displayer.events.lock().unwrap();
+            let event = &events[displayer.on_event];
+            Some(format!(
+                "{}{}",
+                state.storage, event.experiences[event.on_experience].path
+            ))
         }
-        if !item.is_empty() {
-            return Element::from(column![
-                text(with_name).size(50),
-                row![horizontal_space(Length::Fixed(20.0)), lists,]
-            ]);
+        _ => None,
+    }
+}
+
+fn adjustment_panel<'a>(path: &str) -> Element<'a, Message> {
+    let adjustment = decoding::adjustment_of(path);
+    column![
+        row![
+            widget::tooltip(
+                button_from_svg(include_bytes!("./runtime/rotate-right.svg"))
+                    .width(Length::Fixed(40.0))
+                    .on_press(Message::RotateImage),
+                "顺时针旋转 90°",
+                widget::tooltip::Position::Bottom
+            )
+            .style(iced::theme::Container::Box),
+            widget::Button::new(text("还原").size(20))
+                .style(iced::theme::Button::Secondary)
+                .on_press(Message::ResetAdjustment),
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center),
+        row![
+            text("亮度").size(20),
+            widget::Slider::new(-100..=100, adjustment.brightness, Message::AdjustBrightness)
+                .on_release(Message::ApplyAdjustment)
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center),
+        row![
+            text("对比度").size(20),
+            widget::Slider::new(-50.0..=50.0, adjustment.contrast, Message::AdjustContrast)
+                .on_release(Message::ApplyAdjustment)
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center),
+    ]
+    .spacing(5)
+    .align_items(Alignment::Center)
+    .width(Length::Fixed(240.0))
+    .into()
+}
+
+/// 照片里第 `index` 个人的框用的颜色，人名前面的圆点也用这个颜色。
+fn tag_color(index: usize) -> Color {
+    match index % 6 {
+        0 => Color::from_rgb8(240, 134, 80),
+        1 => Color::from_rgb8(120, 158, 204),
+        2 => Color::from_rgb8(85, 143, 128),
+        3 => Color::from_rgb8(240, 135, 132),
+        4 => Color::from_rgb8(127, 130, 187),
+        _ => Color::from_rgb8(255, 215, 121),
+    }
+}
+
+/// 照片里出现的人，有框的排在前面。
+fn people_in(experience: &visiting::Experience) -> Vec<usize> {
+    let mut people: Vec<usize> = experience.regions.iter().map(|region| region.who).collect();
+    for who in &experience.with {
+        if !people.contains(who) {
+            people.push(*who);
         }
     }
-    Element::from(column![])
+    people
+}
+
+fn tagged_regions(path: &str, experience: &visiting::Experience) -> Vec<(iced::Rectangle, Color)> {
+    let rotation = decoding::adjustment_of(path).rotation;
+    experience
+        .regions
+        .iter()
+        .enumerate()
+        .map(|(index, region)| {
+            (
+                decoding::rotate_region(region.rect, rotation),
+                tag_color(index),
+            )
+        })
+        .collect()
+}
+
+fn people_panel<'a>(state: &State, experience: &visiting::Experience) -> Element<'a, Message> {
+    let people = people_in(experience);
+    if people.is_empty() {
+        return Element::from(column![]);
+    }
+    let mut lists = column![].spacing(5);
+    for (index, who) in people.into_iter().enumerate() {
+        let dot = if index < experience.regions.len() {
+            text("●").size(24).style(tag_color(index))
+        } else {
+            text("○").size(24)
+        };
+        lists = lists.push(
+            row![
+                dot,
+                widget::Button::new(text(state.get_name(who)).size(24))
+                    .padding(0)
+                    .style(iced::theme::Button::Text)
+                    .on_press(Message::OpenProfile(who)),
+            ]
+            .spacing(8)
+            .align_items(Alignment::Center),
+        );
+    }
+    column![text("照片里的人").size(30), lists]
+        .spacing(5)
+        .align_items(Alignment::Center)
+        .into()
+}
+
+/// 共同回忆里，当前照片有谁，以及每两人同框的次数。
+fn company_panel<'a>(
+    state: &State,
+    displayer: &VisitingState,
+    experience: &visiting::Experience,
+) -> Element<'a, Message> {
+    if displayer.company.people.len() < 2 {
+        return Element::from(column![]);
+    }
+    let present = if experience.with.is_empty() {
+        String::from("全班合照")
+    } else {
+        let names: Vec<String> = displayer
+            .company
+            .people
+            .iter()
+            .filter(|person| experience.with.contains(person))
+            .map(|&person| state.get_name(person))
+            .collect();
+        format!("同框：{}", names.join("、"))
+    };
+    let mut counts = column![].spacing(2);
+    for &(a, b, count) in &displayer.pair_counts {
+        counts = counts.push(
+            text(format!(
+                "{} & {}：{} 张",
+                state.get_name(a),
+                state.get_name(b),
+                count
+            ))
+            .size(20),
+        );
+    }
+    column![text(present).size(26), text("一起出现过").size(22), counts]
+        .spacing(5)
+        .align_items(Alignment::Center)
+        .into()
+}
+
+fn show_profiles<'a>(item: &[String], with_name: &str) -> Element<'a, Message> {
+    if item.is_empty() {
+        return Element::from(column![]);
+    }
+    let mut lists = column![];
+    for i in item {
+        lists = lists.push(text(i.clone()).size(30));
+    }
+    Element::from(column![
+        text(with_name).size(50),
+        row![horizontal_space(Length::Fixed(20.0)), lists,]
+    ])
 }
@@ -0,0 +1,329 @@
+use crate::export::{self, Destination};
+use crate::{imageviewer, visiting, Message, Stage, State};
+use iced::widget::image::Handle;
+use iced::widget::{self, column, container, row, text};
+use iced::{Alignment, Element, Length};
+use iced_core::image::Data;
+use image::{imageops, Rgb, RgbImage};
+use std::fs;
+use std::path::Path;
+
+/// 横向的格子数
+const COLUMNS: u32 = 60;
+/// 每个格子的边长，放大后能认出照片里的人
+const TILE: u32 = 96;
+/// 每格叠上多少原图的颜色，越大越像原图
+const TINT: f32 = 0.3;
+
+/// 可以拼成马赛克的照片，取自「一起的时光」。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Target {
+    pub path: String,
+    pub label: String,
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label)
+    }
+}
+
+pub fn targets(idxtable: &toml::Table) -> Vec<Target> {
+    let mut targets = vec![];
+    for event in idxtable.get("together_event").unwrap().as_array().unwrap() {
+        let description = event
+            .get("description")
+            .and_then(|d| d.as_str())
+            .unwrap_or_default();
+        let images = event.get("image").unwrap().as_array().unwrap();
+        for (index, img) in images.iter().enumerate() {
+            let label = if images.len() > 1 {
+                format!("{}（{}）", description, index + 1)
+            } else {
+                description.to_string()
+            };
+            targets.push(Target {
+                path: img.as_str().unwrap().to_string(),
+                label,
+            });
+        }
+    }
+    targets
+}
+
+#[derive(Clone, Debug)]
+pub struct Mosaic {
+    pub target: Target,
+    /// 拼好的图只留这一份 RGBA，导出时也从这里取
+    pub handle: Handle,
+    pub width: u32,
+    pub height: u32,
+    /// 上一次导出的结果
+    pub notice: String,
+}
+
+fn walk(folder: &Path, files: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(folder) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            walk(&path, files);
+        } else {
+            files.push(path.to_string_lossy().to_string());
+        }
+    }
+}
+
+/// 已经缓存在本地的照片和头像，不会为了拼图去下载。
+fn cached_images(state: &State) -> Vec<String> {
+    let mut files = vec![];
+    walk(
+        Path::new(&format!("{}/image/experience", state.storage)),
+        &mut files,
+    );
+    walk(
+        Path::new(&format!("{}/image/known_people", state.storage)),
+        &mut files,
+    );
+    for target in targets(&state.idxtable) {
+        let path = format!("{}{}", state.storage, target.path);
+        if Path::new(&path).is_file() {
+            files.push(path);
+        }
+    }
+    files
+}
+
+fn average(img: &RgbImage) -> [f32; 3] {
+    let mut sum = [0.0; 3];
+    for pixel in img.pixels() {
+        for (total, value) in sum.iter_mut().zip(pixel.0) {
+            *total += value as f32;
+        }
+    }
+    let count = (img.width() * img.height()).max(1) as f32;
+    sum.map(|total| total / count)
+}
+
+/// 读成一个格子大小。jpeg 直接按缩小的尺寸解码，省得把整张大图读进内存。
+fn tile_of(file: &str) -> Option<RgbImage> {
+    let bytes = fs::read(file).ok()?;
+    let img = match image::guess_format(&bytes).ok()? {
+        image::ImageFormat::Jpeg => {
+            let mut decoder = image::codecs::jpeg::JpegDecoder::new(&bytes[..]).ok()?;
+            decoder.scale(TILE as u16, TILE as u16).ok()?;
+            image::DynamicImage::from_decoder(decoder).ok()?
+        }
+        _ => image::load_from_memory(&bytes).ok()?,
+    };
+    Some(
+        img.resize_to_fill(TILE, TILE, imageops::FilterType::Triangle)
+            .to_rgb8(),
+    )
+}
+
+fn distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    (0..3)
+        .map(|channel| (a[channel] - b[channel]).powi(2))
+        .sum()
+}
+
+/// 每个格子选颜色最接近的照片，按行给出下标；照片够多时不和左边、上边的格子重复。
+fn choose(cells: &RgbImage, colors: &[[f32; 3]]) -> Vec<usize> {
+    let columns = cells.width();
+    let mut chosen = vec![usize::MAX; (columns * cells.height()) as usize];
+    for (x, y, cell) in cells.enumerate_pixels() {
+        let cell = cell.0.map(|c| c as f32);
+        let left = if x > 0 {
+            chosen[(y * columns + x - 1) as usize]
+        } else {
+            usize::MAX
+        };
+        let above = if y > 0 {
+            chosen[((y - 1) * columns + x) as usize]
+        } else {
+            usize::MAX
+        };
+        chosen[(y * columns + x) as usize] = (0..colors.len())
+            .filter(|&index| colors.len() < 3 || (index != left && index != above))
+            .min_by(|&a, &b| distance(colors[a], cell).total_cmp(&distance(colors[b], cell)))
+            .unwrap();
+    }
+    chosen
+}
+
+/// 把每个格子换成选中的照片，再叠上一点格子本来的颜色。`tiles` 不能为空，大小都一样。
+fn arrange(cells: &RgbImage, tiles: &[RgbImage]) -> RgbImage {
+    let size = tiles[0].width();
+    let colors: Vec<[f32; 3]> = tiles.iter().map(average).collect();
+    let chosen = choose(cells, &colors);
+    let mut mosaic = RgbImage::new(cells.width() * size, cells.height() * size);
+    for (x, y, cell) in cells.enumerate_pixels() {
+        let best = chosen[(y * cells.width() + x) as usize];
+        for (tx, ty, pixel) in tiles[best].enumerate_pixels() {
+            let tinted = Rgb([0usize, 1, 2].map(|channel| {
+                (pixel[channel] as f32 * (1.0 - TINT) + cell[channel] as f32 * TINT) as u8
+            }));
+            mosaic.put_pixel(x * size + tx, y * size + ty, tinted);
+        }
+    }
+    mosaic
+}
+
+/// 用 `files` 拼出 `target`，一张照片都读不出来时返回 `None`。
+fn compose(target: &[u8], files: Vec<String>) -> Option<RgbImage> {
+    let target = image::load_from_memory(target).ok()?.to_rgb8();
+    let rows = (COLUMNS * target.height() / target.width()).max(1);
+    let cells = imageops::resize(&target, COLUMNS, rows, imageops::FilterType::Triangle);
+    let tiles: Vec<RgbImage> = files
+        .iter()
+        .map(String::as_str)
+        .filter_map(tile_of)
+        .collect();
+    if tiles.is_empty() {
+        return None;
+    }
+    Some(arrange(&cells, &tiles))
+}
+
+/// 用缓存里的照片拼出 `target`，完成后在毕业页面上显示。
+pub async fn build(mut state: State, target: Target) -> Result<State, crate::Error> {
+    let url_prefix = state.idxtable.get("url_prefix").unwrap().as_str().unwrap();
+    let bytes = visiting::read_or_download(&state.storage, url_prefix, &target.path)
+        .await
+        .ok_or(crate::Error::APIError)?;
+    let files = cached_images(&state);
+    let pixels = tokio::task::spawn_blocking(move || {
+        compose(&bytes, files).map(|pixels| image::DynamicImage::ImageRgb8(pixels).into_rgba8())
+    })
+    .await?
+    .ok_or(crate::Error::ParseError)?;
+    let (width, height) = pixels.dimensions();
+    let handle = Handle::from_pixels(width, height, pixels.into_raw());
+    if let Stage::Graduated(ref mut vision) = state.stage {
+        vision.mosaic = Some(Mosaic {
+            target,
+            handle,
+            width,
+            height,
+            notice: String::new(),
+        });
+    }
+    Ok(state)
+}
+
+/// 询问保存位置，把拼好的马赛克按原尺寸写成 png。
+pub async fn export(name: String, handle: Handle) -> String {
+    let destination = Destination::File {
+        title: "导出马赛克拼图",
+        name: format!("{}.png", name),
+        filter: ("图片", &["png"]),
+    };
+    export::save_with_notice(destination, |path| async move {
+        let Data::Rgba {
+            width,
+            height,
+            pixels,
+        } = handle.data().clone()
+        else {
+            return Err(String::from("拼图不是解码后的像素"));
+        };
+        tokio::task::spawn_blocking(move || {
+            image::save_buffer_with_format(
+                path,
+                &pixels,
+                width,
+                height,
+                image::ColorType::Rgba8,
+                image::ImageFormat::Png,
+            )
+        })
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+        Ok::<_, String>(format!("，{}×{} 像素", width, height))
+    })
+    .await
+}
+
+pub fn pick<'a>(idxtable: &toml::Table) -> Element<'a, Message> {
+    widget::pick_list(targets(idxtable), None, Message::BuildMosaic)
+        .placeholder("拼一张马赛克...")
+        .text_size(20)
+        .into()
+}
+
+pub fn show_mosaic<'a>(mosaic: &Mosaic) -> Element<'a, Message> {
+    let toolbar = row![
+        widget::Button::new(text("返回全景").size(28))
+            .style(iced::theme::Button::Secondary)
+            .on_press(Message::CloseMosaic)
+            .padding(15),
+        widget::Button::new(text("导出 PNG").size(28))
+            .style(iced::theme::Button::Primary)
+            .on_press(Message::ExportMosaic)
+            .padding(15),
+        text(format!(
+            "{}，由 {} 格照片拼成",
+            mosaic.target.label,
+            (mosaic.width / TILE) * (mosaic.height / TILE)
+        ))
+        .size(24),
+        text(&mosaic.notice).size(20),
+    ]
+    .spacing(15)
+    .align_items(Alignment::Center);
+    container(
+        column![
+            toolbar,
+            imageviewer::Viewer::new(mosaic.handle.clone())
+                .id(imageviewer::Id::new("Mosaic"))
+                .max_scale(8.0)
+                .width(Length::Fill)
+                .height(Length::Fill),
+        ]
+        .spacing(10),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tile(color: [u8; 3]) -> RgbImage {
+        RgbImage::from_pixel(2, 2, Rgb(color))
+    }
+
+    #[test]
+    fn choose_picks_closest_without_repeating_neighbours() {
+        let red = [250.0, 0.0, 0.0];
+        let reddish = [200.0, 40.0, 40.0];
+        let blue = [0.0, 0.0, 250.0];
+        let cells = RgbImage::from_pixel(3, 2, Rgb([255, 0, 0]));
+        // (候选颜色, 每个格子选中的下标)
+        let cases = [
+            (vec![red], vec![0, 0, 0, 0, 0, 0]),
+            (vec![blue, red], vec![1, 1, 1, 1, 1, 1]),
+            (vec![red, reddish, blue], vec![0, 1, 0, 1, 0, 1]),
+        ];
+        for (colors, chosen) in cases {
+            assert_eq!(choose(&cells, &colors), chosen, "{:?}", colors);
+        }
+    }
+
+    #[test]
+    fn arrange_places_tinted_tiles() {
+        let mut cells = RgbImage::from_pixel(2, 1, Rgb([0, 0, 0]));
+        cells.put_pixel(1, 0, Rgb([250, 250, 250]));
+        let mosaic = arrange(&cells, &[tile([0, 0, 0]), tile([205, 205, 205])]);
+        assert_eq!(mosaic.dimensions(), (4, 2));
+        assert_eq!(mosaic.get_pixel(1, 1), &Rgb([0, 0, 0]));
+        // 0.7 × 205 + 0.3 × 250 = 218.5
+        assert_eq!(mosaic.get_pixel(2, 0), &Rgb([218, 218, 218]));
+    }
+}
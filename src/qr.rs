@@ -0,0 +1,66 @@
+use crate::overlay::{self, Anchor};
+use crate::Message;
+use iced::widget::{self, column, container, horizontal_space, qr_code, text};
+use iced::{Alignment, Element, Length};
+use std::sync::Arc;
+
+/// 投影或屏幕上的二维码，用手机扫一扫就能打开链接。
+#[derive(Clone, Debug)]
+pub struct Popover {
+    pub link: String,
+    /// 链接太长时生成不了二维码，这里存着原因，弹窗里照样显示链接
+    data: Result<Arc<qr_code::Data>, String>,
+}
+
+impl Popover {
+    pub fn new(link: String) -> Popover {
+        let data = qr_code::Data::new(&link)
+            .map(Arc::new)
+            .map_err(|err| format!("无法生成二维码：{}", err));
+        Popover { link, data }
+    }
+}
+
+fn card(popover: &Popover) -> Element<Message> {
+    container(
+        column![
+            match &popover.data {
+                Ok(data) => column![text("用手机扫一扫").size(30), qr_code(data).cell_size(8),]
+                    .spacing(15)
+                    .align_items(Alignment::Center),
+                Err(err) => column![text(err).size(24)],
+            },
+            text(&popover.link).size(16),
+            widget::Button::new(text("关闭").size(24))
+                .style(iced::theme::Button::Secondary)
+                .on_press(Message::HideQrCode)
+                .padding(10),
+        ]
+        .spacing(15)
+        .align_items(Alignment::Center)
+        .max_width(480.0),
+    )
+    .padding(25)
+    .style(iced::theme::Container::Box)
+    .into()
+}
+
+pub fn qr_over<'a>(
+    popover: Option<&'a Popover>,
+    content: Element<'a, Message>,
+) -> Element<'a, Message> {
+    overlay::Component::new(content, move || match popover {
+        Some(popover) => card(popover),
+        None => horizontal_space(Length::Fixed(0.0)).into(),
+    })
+    .anchor(Anchor::Center)
+    .offset(0.0)
+    .hide(popover.is_none())
+    .into()
+}
+
+/// 在线的照片地址，和下载时用的一致。
+pub fn photo_link(idxtable: &toml::Table, path: &str) -> String {
+    let url_prefix = idxtable.get("url_prefix").unwrap().as_str().unwrap();
+    format!("{}{}", url_prefix, path)
+}
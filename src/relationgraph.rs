@@ -0,0 +1,462 @@
+use crate::choosing::{Avatar, Profile};
+use crate::Message;
+use iced::widget::canvas::{self, Canvas, Frame, Geometry, Path, Stroke};
+use iced::{alignment, Color, Element, Length, Point, Rectangle, Renderer, Size, Theme, Vector};
+use iced_core::event;
+use iced_core::layout::{self, Layout};
+use iced_core::mouse;
+use iced_core::renderer::{self, Renderer as _};
+use iced_core::widget::{Tree, Widget};
+use iced_core::{image as core_image, Clipboard, Event, Shell};
+
+/// 头像在缩放为 1 时的半径
+const NODE_RADIUS: f32 = 32.0;
+/// 布局时所有人大致分布在边长为这么多的正方形里
+const AREA: f32 = 900.0;
+const ITERATIONS: usize = 300;
+const MIN_SCALE: f32 = 0.3;
+const MAX_SCALE: f32 = 4.0;
+/// 评价没有关系名，统一归为这一类
+pub const COMMENT_KIND: &str = "评价";
+pub const ALL_KINDS: &str = "全部关系";
+
+/// 从 `by`（或评价的 `from`）指向被描述的人。
+#[derive(Clone, Debug)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub kind: String,
+}
+
+/// 关系图的布局与当前视角。
+#[derive(Clone, Debug)]
+pub struct Network {
+    // 下标与头像的编号相同，0 号不用
+    positions: Vec<Point>,
+    edges: Vec<Edge>,
+    pub kinds: Vec<String>,
+    pub filter: Option<String>,
+    scale: f32,
+    offset: Vector,
+}
+
+fn length(vector: Vector) -> f32 {
+    (vector.x * vector.x + vector.y * vector.y).sqrt()
+}
+
+fn collect_edges(profiles: &[Profile], count: usize) -> Vec<Edge> {
+    let mut edges: Vec<Edge> = vec![];
+    let mut push = |from: usize, to: usize, kind: &str| {
+        if from == 0 || from >= count || from == to {
+            return;
+        }
+        let exists = edges
+            .iter()
+            .any(|edge| edge.from == from && edge.to == to && edge.kind == kind);
+        if !exists {
+            edges.push(Edge {
+                from,
+                to,
+                kind: kind.to_string(),
+            });
+        }
+    };
+    for (to, profile) in profiles.iter().enumerate().take(count).skip(1) {
+        for relation in &profile.relationship {
+            push(relation.by, to, &relation.is);
+        }
+        for comment in &profile.comment {
+            push(comment.from, to, COMMENT_KIND);
+        }
+    }
+    edges
+}
+
+/// Fruchterman–Reingold 力导向布局：所有人互相排斥，有关系的人互相吸引。
+fn force_directed(count: usize, edges: &[Edge]) -> Vec<Point> {
+    let nodes = count.saturating_sub(1).max(1) as f32;
+    let ideal = (AREA * AREA / nodes).sqrt();
+    // 初始时排成一圈，这样每次打开的布局都一样
+    let mut positions = vec![Point::ORIGIN; count];
+    for (i, position) in positions.iter_mut().enumerate().skip(1) {
+        let angle = i as f32 / nodes * std::f32::consts::TAU;
+        *position = Point::new(angle.cos() * AREA / 2.0, angle.sin() * AREA / 2.0);
+    }
+    let mut temperature = AREA / 10.0;
+    for _ in 0..ITERATIONS {
+        let mut displacement = vec![Vector::new(0.0, 0.0); count];
+        for i in 1..count {
+            for j in i + 1..count {
+                let delta = positions[i] - positions[j];
+                let distance = length(delta).max(0.01);
+                let push = delta * (ideal * ideal / distance / distance);
+                displacement[i] = displacement[i] + push;
+                displacement[j] = displacement[j] - push;
+            }
+        }
+        for edge in edges {
+            let delta = positions[edge.to] - positions[edge.from];
+            let distance = length(delta).max(0.01);
+            let pull = delta * (distance / ideal);
+            displacement[edge.from] = displacement[edge.from] + pull;
+            displacement[edge.to] = displacement[edge.to] - pull;
+        }
+        for i in 1..count {
+            let distance = length(displacement[i]).max(0.01);
+            positions[i] = positions[i] + displacement[i] * (distance.min(temperature) / distance);
+        }
+        temperature *= 0.98;
+    }
+    positions
+}
+
+impl Network {
+    pub fn new(profiles: &[Profile], count: usize) -> Self {
+        let edges = collect_edges(profiles, count);
+        let mut kinds: Vec<String> = vec![];
+        for edge in &edges {
+            if !kinds.contains(&edge.kind) {
+                kinds.push(edge.kind.clone());
+            }
+        }
+        Network {
+            positions: force_directed(count, &edges),
+            edges,
+            kinds,
+            filter: None,
+            scale: 0.6,
+            offset: Vector::new(0.0, 0.0),
+        }
+    }
+
+    pub fn pan(&mut self, delta: Vector) {
+        self.offset = self.offset + delta;
+    }
+
+    /// 缩放时让 `anchor`（相对于画布中心）下面的那个点保持不动。
+    pub fn zoom(&mut self, factor: f32, anchor: Vector) {
+        let scale = (self.scale * factor).clamp(MIN_SCALE, MAX_SCALE);
+        let ratio = scale / self.scale;
+        self.offset = anchor - (anchor - self.offset) * ratio;
+        self.scale = scale;
+    }
+
+    fn project(&self, bounds: Size, num: usize) -> Point {
+        let center = Point::new(bounds.width / 2.0, bounds.height / 2.0);
+        let position = self.positions[num];
+        center + self.offset + Vector::new(position.x, position.y) * self.scale
+    }
+
+    fn radius(&self) -> f32 {
+        NODE_RADIUS * self.scale.max(0.6)
+    }
+
+    fn node_at(&self, bounds: Size, cursor: Point) -> Option<usize> {
+        (1..self.positions.len())
+            .find(|&num| length(cursor - self.project(bounds, num)) <= self.radius())
+    }
+
+    fn visible_edges(&self) -> impl Iterator<Item = &Edge> {
+        self.edges.iter().filter(|edge| match &self.filter {
+            Some(kind) => &edge.kind == kind,
+            None => true,
+        })
+    }
+
+    pub fn filter_options(&self) -> Vec<String> {
+        let mut options = vec![String::from(ALL_KINDS)];
+        options.extend(self.kinds.iter().cloned());
+        options
+    }
+}
+
+/// 画布上的连线、关系名和姓名；头像由 [`Graph`] 画在上面。
+struct Edges<'a> {
+    network: &'a Network,
+    avatars: &'a [Avatar],
+}
+
+impl<'a> canvas::Program<Message> for Edges<'a> {
+    // 正在拖动时上一次的光标位置
+    type State = Option<Point>;
+
+    fn update(
+        &self,
+        grabbed: &mut Option<Point>,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (event::Status, Option<Message>) {
+        if let canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) = event {
+            *grabbed = None;
+            return (event::Status::Ignored, None);
+        }
+        let Some(position) = cursor.position_in(bounds) else {
+            return (event::Status::Ignored, None);
+        };
+        match event {
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                if let Some(num) = self.network.node_at(bounds.size(), position) {
+                    return (event::Status::Captured, Some(Message::ChoseCharacter(num)));
+                }
+                *grabbed = Some(position);
+                (event::Status::Captured, None)
+            }
+            canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) => match *grabbed {
+                Some(previous) => {
+                    *grabbed = Some(position);
+                    (
+                        event::Status::Captured,
+                        Some(Message::GraphMoved(position - previous)),
+                    )
+                }
+                None => (event::Status::Ignored, None),
+            },
+            canvas::Event::Mouse(mouse::Event::WheelScrolled { delta }) => {
+                let y = match delta {
+                    mouse::ScrollDelta::Lines { y, .. } => y,
+                    mouse::ScrollDelta::Pixels { y, .. } => y / 60.0,
+                };
+                let anchor = position - Point::new(bounds.width / 2.0, bounds.height / 2.0);
+                (
+                    event::Status::Captured,
+                    Some(Message::GraphZoomed(1.1_f32.powf(y), anchor)),
+                )
+            }
+            _ => (event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(
+        &self,
+        _grabbed: &Option<Point>,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let palette = theme.extended_palette();
+        let line_color = Color {
+            a: 0.6,
+            ..palette.primary.base.color
+        };
+        let radius = self.network.radius();
+        let size = bounds.size();
+        for edge in self.network.visible_edges() {
+            let from = self.network.project(size, edge.from);
+            let to = self.network.project(size, edge.to);
+            let delta = to - from;
+            let distance = length(delta);
+            if distance <= radius * 2.0 {
+                continue;
+            }
+            let unit = delta * (1.0 / distance);
+            let start = from + unit * radius;
+            let tip = to - unit * radius;
+            frame.stroke(
+                &Path::line(start, tip),
+                Stroke::default().with_width(2.0).with_color(line_color),
+            );
+            // 箭头指向被描述的人
+            let normal = Vector::new(-unit.y, unit.x);
+            let base = tip - unit * 12.0;
+            frame.fill(
+                &Path::new(|builder| {
+                    builder.move_to(tip);
+                    builder.line_to(base + normal * 6.0);
+                    builder.line_to(base - normal * 6.0);
+                    builder.close();
+                }),
+                line_color,
+            );
+            frame.fill_text(canvas::Text {
+                content: edge.kind.clone(),
+                position: start + delta * 0.5 - unit * radius,
+                color: palette.background.base.text,
+                size: 18.0.into(),
+                horizontal_alignment: alignment::Horizontal::Center,
+                vertical_alignment: alignment::Vertical::Bottom,
+                ..Default::default()
+            });
+        }
+        for num in 1..self.avatars.len() {
+            let center = self.network.project(size, num);
+            frame.fill(
+                &Path::circle(center, radius + 3.0),
+                palette.primary.weak.color,
+            );
+            frame.fill_text(canvas::Text {
+                content: self.avatars[num].name.clone(),
+                position: center + Vector::new(0.0, radius + 6.0),
+                color: palette.background.base.text,
+                size: 20.0.into(),
+                horizontal_alignment: alignment::Horizontal::Center,
+                vertical_alignment: alignment::Vertical::Top,
+                ..Default::default()
+            });
+        }
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(
+        &self,
+        grabbed: &Option<Point>,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> mouse::Interaction {
+        if grabbed.is_some() {
+            return mouse::Interaction::Grabbing;
+        }
+        match cursor.position_in(bounds) {
+            Some(position) if self.network.node_at(bounds.size(), position).is_some() => {
+                mouse::Interaction::Pointer
+            }
+            Some(_) => mouse::Interaction::Grab,
+            None => mouse::Interaction::default(),
+        }
+    }
+}
+
+/// 可拖动、缩放的关系图，点击头像打开 ta 的资料。
+#[allow(missing_debug_implementations)]
+pub struct Graph<'a> {
+    network: &'a Network,
+    avatars: &'a [Avatar],
+    canvas: Element<'a, Message>,
+}
+
+impl<'a> Graph<'a> {
+    pub fn new(network: &'a Network, avatars: &'a [Avatar]) -> Self {
+        Graph {
+            network,
+            avatars,
+            canvas: Canvas::new(Edges { network, avatars })
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into(),
+        }
+    }
+}
+
+impl<'a> Widget<Message, Renderer> for Graph<'a> {
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.canvas)]
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(&[&self.canvas]);
+    }
+
+    fn width(&self) -> Length {
+        Length::Fill
+    }
+
+    fn height(&self) -> Length {
+        Length::Fill
+    }
+
+    fn layout(
+        &self,
+        tree: &mut Tree,
+        renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        self.canvas
+            .as_widget()
+            .layout(&mut tree.children[0], renderer, limits)
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        self.canvas.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event,
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        )
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.canvas.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout,
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        self.canvas.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor,
+            viewport,
+        );
+        let bounds = layout.bounds();
+        let radius = self.network.radius();
+        renderer.with_layer(bounds, |renderer| {
+            for num in 1..self.avatars.len() {
+                let photo = &self.avatars[num].photo;
+                let Size { width, height } = core_image::Renderer::dimensions(renderer, photo);
+                if width == 0 || height == 0 {
+                    continue;
+                }
+                // 照片等比缩放到直径大小的正方形里
+                let ratio = radius * 2.0 / width.max(height) as f32;
+                let size = Size::new(width as f32 * ratio, height as f32 * ratio);
+                let center = self.network.project(bounds.size(), num);
+                core_image::Renderer::draw(
+                    renderer,
+                    photo.clone(),
+                    core_image::FilterMethod::Linear,
+                    Rectangle {
+                        x: bounds.x + center.x - size.width / 2.0,
+                        y: bounds.y + center.y - size.height / 2.0,
+                        width: size.width,
+                        height: size.height,
+                    },
+                );
+            }
+        });
+    }
+}
+
+impl<'a> From<Graph<'a>> for Element<'a, Message> {
+    fn from(graph: Graph<'a>) -> Self {
+        Element::new(graph)
+    }
+}
@@ -0,0 +1,375 @@
+use crate::configs::modal::Modal;
+use crate::terms::Term;
+use crate::{choosing, graduation, visiting, Message, Stage, State};
+use iced::widget::{self, column, container, scrollable, text, text_input};
+use iced::{Element, Length};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use toml::value::Table;
+
+const MAX_RESULTS: usize = 50;
+
+/// 搜索结果跳转到的位置。
+#[derive(Clone, Debug)]
+pub enum Target {
+    Profile(usize),
+    TogetherEvent(usize),
+    Experience { person: usize, description: String },
+    Panorama(usize, usize),
+}
+
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub title: String,
+    pub content: String,
+    pub target: Target,
+    lowercase: String,
+}
+
+impl Entry {
+    fn new(title: String, content: String, target: Target) -> Self {
+        Entry {
+            lowercase: format!("{}\n{}", title, content).to_lowercase(),
+            title,
+            content,
+            target,
+        }
+    }
+
+    /// 匹配位置附近的一小段文字。
+    fn snippet(&self, query: &str) -> String {
+        let chars: Vec<char> = self.content.chars().collect();
+        let lowercase: String = self.content.to_lowercase();
+        let position = match lowercase.find(query) {
+            Some(byte_index) => lowercase[..byte_index].chars().count(),
+            None => 0,
+        };
+        let left = position.saturating_sub(15);
+        let right = std::cmp::min(position + 35, chars.len());
+        let mut snippet: String = chars[left..right].iter().collect();
+        snippet = snippet.replace('\n', " ");
+        if left > 0 {
+            snippet.insert(0, '…');
+        }
+        if right < chars.len() {
+            snippet.push('…');
+        }
+        snippet
+    }
+}
+
+/// 任意阶段都能打开的全局搜索面板。
+#[derive(Clone, Debug, Default)]
+pub struct Palette {
+    pub shown: bool,
+    pub query: String,
+    pub index: Vec<Entry>,
+    pub results: Vec<usize>,
+}
+
+impl Palette {
+    pub fn search(&mut self) {
+        let query = self.query.trim().to_lowercase();
+        self.results.clear();
+        if query.is_empty() {
+            return;
+        }
+        // 标题命中的排在前面
+        let mut in_content = vec![];
+        for (index, entry) in self.index.iter().enumerate() {
+            if entry.title.to_lowercase().contains(&query) {
+                self.results.push(index);
+            } else if entry.lowercase.contains(&query) {
+                in_content.push(index);
+            }
+        }
+        self.results.append(&mut in_content);
+        self.results.truncate(MAX_RESULTS);
+    }
+}
+
+pub fn palette_id() -> text_input::Id {
+    text_input::Id::new("GlobalSearch")
+}
+
+/// 读取本地的文本，没有的话下载并存下来；下载失败（包括 404 之类的错误页）时返回 `None`，
+/// 不会把错误页当成内容缓存起来。
+pub async fn read_or_fetch(path: &Path, url: &str) -> Option<String> {
+    if path.is_file() {
+        return fs::read_to_string(path).ok();
+    }
+    let response = reqwest::get(url).await.ok()?.error_for_status().ok()?;
+    let text = response.text().await.ok()?;
+    fs::create_dir_all(path.parent()?).ok()?;
+    let mut file = fs::File::create(path).ok()?;
+    file.write_all(text.as_bytes()).ok()?;
+    Some(text)
+}
+
+fn index_profile(num: usize, name: &str, profile: &choosing::Profile) -> Vec<Entry> {
+    let mut entries = vec![];
+    let mut push = |section: &str, content: String| {
+        if !content.is_empty() {
+            entries.push(Entry::new(
+                format!("{} · {}", name, section),
+                content,
+                Target::Profile(num),
+            ));
+        }
+    };
+    push("小日常", profile.plots.join("\n"));
+    push("自传", profile.introduction.clone().unwrap_or_default());
+    if let Some(anecdote) = &profile.anecdote {
+        let answers: Vec<&str> = [&anecdote.interests, &anecdote.want, &anecdote.embarrassment]
+            .into_iter()
+            .flatten()
+            .map(|answer| answer.as_str())
+            .collect();
+        push("关于 ta", answers.join("\n"));
+    }
+    let descriptions: Vec<&str> = profile
+        .comment
+        .iter()
+        .map(|comment| comment.description.as_str())
+        .collect();
+    push("大家对 ta 的评价", descriptions.join("\n"));
+    let reviews: Vec<&str> = profile
+        .reviews
+        .iter()
+        .map(|review| review.content.as_str())
+        .collect();
+    push("班主任评语", reviews.join("\n"));
+    entries
+}
+
+fn index_events(events_table: &toml::Table, entries: &mut Vec<Entry>) {
+    for kind in ["experience", "event"] {
+        let Some(array) = events_table.get(kind).and_then(|a| a.as_array()) else {
+            continue;
+        };
+        for event in array {
+            let Some(description) = event.get("description").and_then(|d| d.as_str()) else {
+                continue;
+            };
+            // 跟着照片里的第一个人去看这段经历；全班的经历谁的时间线里都有，就去 1 号那里看
+            let person = event
+                .get("image")
+                .and_then(|images| images.as_array())
+                .and_then(|images| {
+                    images.iter().find_map(|img| {
+                        img.get("with")?
+                            .as_array()?
+                            .first()?
+                            .as_integer()
+                            .map(|person| person as usize)
+                    })
+                })
+                .unwrap_or(1);
+            entries.push(Entry::new(
+                String::from("经历"),
+                description.to_string(),
+                Target::Experience {
+                    person,
+                    description: description.to_string(),
+                },
+            ));
+        }
+    }
+}
+
+pub async fn build_index(storage: String, idxtable: Table, terms: Vec<Term>) -> Vec<Entry> {
+    let mut entries = vec![];
+    let url_prefix = idxtable.get("url_prefix").unwrap().as_str().unwrap();
+    let profile_names = idxtable.get("profile").unwrap().as_table().unwrap();
+    for num in 1..=profile_names.len() {
+        let name = profile_names
+            .get(&num.to_string())
+            .unwrap()
+            .as_str()
+            .unwrap();
+        let profile_path = format!("{}/profile/{}.toml", storage, num);
+        let profile_url = format!("{}/profile/{}.toml", url_prefix, num);
+        let Some(profile_text) = read_or_fetch(Path::new(&profile_path), &profile_url).await else {
+            continue;
+        };
+        let profile = choosing::Profile::parse(num, &profile_text, profile_names.len() + 1, &terms)
+            .unwrap_or_default();
+        entries.push(Entry::new(
+            name.to_string(),
+            profile.name_en.clone().unwrap_or_default(),
+            Target::Profile(num),
+        ));
+        entries.append(&mut index_profile(num, name, &profile));
+    }
+    let together_events = idxtable.get("together_event").unwrap().as_array().unwrap();
+    for (index, event) in together_events.iter().enumerate() {
+        if let Some(description) = event.get("description").and_then(|d| d.as_str()) {
+            entries.push(Entry::new(
+                String::from("一起的时光"),
+                description.to_string(),
+                Target::TogetherEvent(index),
+            ));
+        }
+    }
+    let events_path = format!("{}/events.toml", storage);
+    let events_url = format!("{}/events.toml", url_prefix);
+    if let Some(events_text) = read_or_fetch(Path::new(&events_path), &events_url).await {
+        if let Ok(events_table) = events_text.parse::<toml::Table>() {
+            index_events(&events_table, &mut entries);
+        }
+    }
+    if let Some(panoramas) = idxtable.get("panorama").and_then(|p| p.as_array()) {
+        for (location, pan) in panoramas.iter().enumerate() {
+            let images = pan.get("image").unwrap().as_array().unwrap();
+            for (on_image, name) in images.iter().enumerate() {
+                let name = name.as_str().unwrap();
+                entries.push(Entry::new(
+                    name.strip_suffix(".jpg").unwrap_or(name).to_string(),
+                    String::from("全景"),
+                    Target::Panorama(location, on_image),
+                ));
+            }
+        }
+    }
+    entries
+}
+
+/// 切换到搜索结果所在的阶段，并停在对应的条目上。
+pub async fn jump(state: State, target: Target) -> Result<State, crate::Error> {
+    match target {
+        Target::Profile(person) => {
+            choosing::get_configs(
+                Some(person),
+                iced::widget::scrollable::RelativeOffset::START,
+                state,
+            )
+            .await
+        }
+        Target::TogetherEvent(on_event) => {
+            let mut state = State::get_idx(Some(state)).await?;
+            if let Stage::EntryEvents(ref mut entry) = state.stage {
+                entry.on_event = on_event;
+            }
+            Ok(state)
+        }
+        Target::Experience {
+            person,
+            description,
+        } => {
+            let state = choosing::get_configs(
+                Some(person),
+                iced::widget::scrollable::RelativeOffset::START,
+                state,
+            )
+            .await?;
+            let mut state = visiting::get_queue(state).await?;
+            if let Stage::ShowingPlots(ref mut displayer) = state.stage {
+                let found = displayer
+                    .events
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .position(|event| event.description == description);
+                if let Some(found) = found {
+                    displayer.on_event = found;
+                }
+            }
+            visiting::load_images(&mut state);
+            visiting::wait_for_current(&state).await?;
+            Ok(state)
+        }
+        Target::Panorama(location, on_image) => {
+            let mut state = graduation::load_map(state).await?;
+            graduation::ON_LOCATION.store(location, Ordering::Relaxed);
+            if let Stage::Graduated(ref mut vision) = state.stage {
+                vision.on_image = on_image;
+            }
+            Ok(state)
+        }
+    }
+}
+
+pub fn palette_over<'a>(
+    palette: &'a Palette,
+    content: Element<'a, Message>,
+) -> Element<'a, Message> {
+    let query = palette.query.trim().to_lowercase();
+    let mut results = column![].spacing(5);
+    for &index in &palette.results {
+        let entry = &palette.index[index];
+        results = results.push(
+            widget::Button::new(column![
+                text(&entry.title).size(26),
+                text(entry.snippet(&query)).size(20)
+            ])
+            .width(Length::Fill)
+            .style(iced::theme::Button::Text)
+            .on_press(Message::SearchSelected(index)),
+        );
+    }
+    let hint = if palette.index.is_empty() {
+        "正在建立索引..."
+    } else if !query.is_empty() && palette.results.is_empty() {
+        "没有找到相关内容"
+    } else {
+        ""
+    };
+    let mut input = text_input("搜索同学、事件、地点...", &palette.query)
+        .id(palette_id())
+        .on_input(Message::SearchEdited)
+        .size(28)
+        .padding(10);
+    if let Some(first) = palette.results.first() {
+        input = input.on_submit(Message::SearchSelected(*first));
+    }
+    let modal = container(
+        column![
+            text("搜索「Ctrl + F」").size(38),
+            input,
+            text(hint).size(20),
+            scrollable(results).height(Length::Fixed(500.0)),
+        ]
+        .spacing(15),
+    )
+    .width(Length::Fixed(700.0))
+    .padding(10)
+    .style(iced::theme::Container::Box);
+    Modal::new(content, modal)
+        .on_blur(Message::HideSearch)
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whole_class_events_are_indexed() {
+        let events: toml::Table = r#"
+            [[experience]]
+            description = "运动会"
+            image = [{ path = "1.jpg", with = [3, 4] }]
+
+            [[event]]
+            description = "毕业典礼"
+            image = [{ path = "/image/event/2.jpg" }]
+        "#
+        .parse()
+        .unwrap();
+        let mut entries = vec![];
+        index_events(&events, &mut entries);
+        let people: Vec<(&str, usize)> = entries
+            .iter()
+            .map(|entry| match &entry.target {
+                Target::Experience {
+                    person,
+                    description,
+                } => (description.as_str(), *person),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(people, [("运动会", 3), ("毕业典礼", 1)]);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::choosing::Avatar;
+use crate::terms::Term;
+use crate::{validation, Message};
+use iced::widget::{self, column, container, horizontal_space, row, scrollable, text};
+use iced::{Alignment, Element, Length};
+use toml::value::Table;
+
+const SEAT_WIDTH: f32 = 150.0;
+
+/// 一个学期的座位表，从讲台往后一排一排地写，`0` 表示空座位。
+#[derive(Clone, Debug)]
+pub struct Layout {
+    pub term: String,
+    pub rows: Vec<Vec<usize>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Chart {
+    pub layouts: Vec<Layout>,
+    pub on_layout: usize,
+}
+
+impl Chart {
+    /// 读取 index.toml 中的 `[[seating]]`，如
+    /// `{ term = "七年级上学期", rows = [[1, 2, 0, 3], [4, 5, 6, 7]] }`，
+    /// 按学期先后排列，默认显示最近的一个学期。
+    pub fn new(idxtable: &Table, terms: &[Term], count: usize) -> Chart {
+        let mut layouts = vec![];
+        let array = idxtable
+            .get("seating")
+            .and_then(|seating| seating.as_array());
+        for layout in array.into_iter().flatten() {
+            let Some(term) = layout.get("term").and_then(|term| term.as_str()) else {
+                validation::warn(String::from("index.toml: `seating` 缺少 `term`"));
+                continue;
+            };
+            let Some(lines) = layout.get("rows").and_then(|rows| rows.as_array()) else {
+                validation::warn(format!("index.toml: {} 的座位表缺少 `rows`", term));
+                continue;
+            };
+            let mut rows = vec![];
+            for line in lines {
+                let mut seats = vec![];
+                for seat in line.as_array().into_iter().flatten() {
+                    let seat = seat.as_integer().unwrap_or(0) as usize;
+                    if seat >= count {
+                        validation::warn(format!(
+                            "index.toml: {} 的座位表中编号 {} 不存在",
+                            term, seat
+                        ));
+                        seats.push(0);
+                    } else {
+                        seats.push(seat);
+                    }
+                }
+                rows.push(seats);
+            }
+            layouts.push(Layout {
+                term: term.to_string(),
+                rows,
+            });
+        }
+        layouts.sort_by_key(|layout| {
+            terms
+                .iter()
+                .position(|term| term.name == layout.term)
+                .unwrap_or(usize::MAX)
+        });
+        Chart {
+            on_layout: layouts.len().saturating_sub(1),
+            layouts,
+        }
+    }
+
+    pub fn options(&self) -> Vec<String> {
+        self.layouts
+            .iter()
+            .map(|layout| layout.term.clone())
+            .collect()
+    }
+
+    pub fn select(&mut self, term: &str) {
+        if let Some(found) = self.layouts.iter().position(|layout| layout.term == term) {
+            self.on_layout = found;
+        }
+    }
+}
+
+/// 坐在 `num` 号座位上的人，`num` 为 0 或者没有这个人的头像时为 `None`。
+fn seated(num: usize, avatars: &[Avatar]) -> Option<&Avatar> {
+    avatars.get(num).filter(|_| num != 0)
+}
+
+/// 没有人坐时留出空座位。
+fn seat<'a>(num: usize, avatars: &[Avatar]) -> Element<'a, Message> {
+    let Some(avatar) = seated(num, avatars) else {
+        return horizontal_space(Length::Fixed(SEAT_WIDTH)).into();
+    };
+    widget::Button::new(
+        column![
+            widget::image(avatar.photo.clone()).height(Length::Fixed(120.0)),
+            text(avatar.name.clone()).size(22),
+        ]
+        .align_items(Alignment::Center),
+    )
+    .width(Length::Fixed(SEAT_WIDTH))
+    .style(iced::theme::Button::Secondary)
+    .padding(5)
+    .on_press(Message::ChoseCharacter(num))
+    .into()
+}
+
+pub fn seating_chart<'a>(chart: &Chart, avatars: &[Avatar]) -> Element<'a, Message> {
+    let Some(layout) = chart.layouts.get(chart.on_layout) else {
+        return container(text("index.toml 里还没有座位表").size(30))
+            .width(Length::Fill)
+            .center_x()
+            .into();
+    };
+    let mut classroom = column![container(text("讲台").size(28))
+        .padding(10)
+        .style(iced::theme::Container::Box)]
+    .spacing(10)
+    .align_items(Alignment::Center);
+    for line in &layout.rows {
+        let mut seats = row![].spacing(10);
+        for &num in line {
+            seats = seats.push(seat(num, avatars));
+        }
+        classroom = classroom.push(seats);
+    }
+    scrollable(container(classroom).width(Length::Fill).center_x())
+        .height(Length::Fill)
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::choosing::{avatar, Profile};
+    use time::macros::date;
+
+    #[test]
+    fn layouts_follow_terms_and_blank_unknown_seats() {
+        let idxtable: Table = toml::from_str(
+            r#"
+            [[seating]]
+            term = "七年级下学期"
+            rows = [[1, 2], [3, 9]]
+
+            [[seating]]
+            term = "七年级上学期"
+            rows = [[2, 1, 0]]
+            "#,
+        )
+        .unwrap();
+        let terms = [
+            Term {
+                name: String::from("七年级上学期"),
+                start: date!(2020 - 09 - 01),
+                end: date!(2021 - 01 - 31),
+            },
+            Term {
+                name: String::from("七年级下学期"),
+                start: date!(2021 - 02 - 01),
+                end: date!(2021 - 08 - 31),
+            },
+        ];
+        let mut chart = Chart::new(&idxtable, &terms, 4);
+        assert_eq!(chart.options(), ["七年级上学期", "七年级下学期"]);
+        assert_eq!(chart.on_layout, 1);
+        assert_eq!(chart.layouts[1].rows, vec![vec![1, 2], vec![3, 0]]);
+        chart.select("七年级上学期");
+        assert_eq!(chart.on_layout, 0);
+    }
+
+    #[test]
+    fn seat_without_avatar_is_left_empty() {
+        let avatars = vec![
+            avatar("", &Profile::default()),
+            avatar("张三", &Profile::default()),
+        ];
+        for num in [0, 2, 100] {
+            assert!(seated(num, &avatars).is_none(), "{}", num);
+        }
+        assert_eq!(seated(1, &avatars).unwrap().name, "张三");
+    }
+}
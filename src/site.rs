@@ -0,0 +1,319 @@
+use crate::choosing::Profile;
+use crate::export::{self, Destination};
+use crate::yearbook::{document, escape};
+use crate::{search, visiting, State};
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// 导出时图片的最长边
+const MAX_SIDE: u32 = 1600;
+
+/// index.toml 中的姓名，下标与资料编号一致，第 0 个为空；写错了的也留空，导出时跳过。
+pub fn names(state: &State) -> Vec<String> {
+    let mut names = vec![String::new()];
+    let Some(table) = state.idxtable.get("profile").and_then(|p| p.as_table()) else {
+        return names;
+    };
+    for num in 1..=table.len() {
+        names.push(
+            table
+                .get(&num.to_string())
+                .and_then(|name| name.as_str())
+                .unwrap_or_default()
+                .to_string(),
+        );
+    }
+    names
+}
+
+/// 所有人的资料，下标与 [`names`] 一致。
+pub async fn load_profiles(state: &State, count: usize) -> Vec<Profile> {
+    let url_prefix = state.idxtable.get("url_prefix").unwrap().as_str().unwrap();
+    let mut profiles = vec![Profile::default()];
+    for num in 1..count {
+        let path = format!("{}/profile/{}.toml", state.storage, num);
+        let url = format!("{}/profile/{}.toml", url_prefix, num);
+        let text = search::read_or_fetch(Path::new(&path), &url).await;
+        profiles.push(
+            text.and_then(|text| Profile::parse(num, &text, count, &state.terms))
+                .unwrap_or_default(),
+        );
+    }
+    profiles
+}
+
+/// 本地的 `relative` 在导出目录里的位置，统一转成 jpg。
+/// 保留原来的扩展名，免得 a.png 和 a.jpg 写到同一个文件。
+fn image_target(relative: &str) -> String {
+    format!("images/{}.jpg", relative.trim_start_matches('/'))
+}
+
+/// 把图片缩到最长边不超过 `max_side`，编码成 jpg；解不开时返回 `None`。
+pub fn shrink(source: &[u8], max_side: u32) -> Option<Vec<u8>> {
+    let img = image::load_from_memory(source).ok()?;
+    let img = if img.width() > max_side || img.height() > max_side {
+        img.resize(max_side, max_side, image::imageops::FilterType::Triangle)
+    } else {
+        img
+    };
+    let mut bytes = vec![];
+    image::DynamicImage::ImageRgb8(img.to_rgb8())
+        .write_to(
+            &mut std::io::Cursor::new(&mut bytes),
+            image::ImageOutputFormat::Jpeg(85),
+        )
+        .ok()?;
+    Some(bytes)
+}
+
+/// 缩小后另存为 jpg；解不开的文件原样复制。
+fn resize_into(source: &[u8], target: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(target.parent().unwrap())?;
+    match shrink(source, MAX_SIDE) {
+        Some(shrunk) => fs::write(target, shrunk),
+        None => fs::write(target, source),
+    }
+}
+
+/// 把图片放进导出目录，返回相对于站点根目录的路径。
+struct Images {
+    root: PathBuf,
+    storage: String,
+    url_prefix: String,
+}
+
+impl Images {
+    /// 本地没有的先下载下来。
+    async fn put(&self, relative: &str) -> Option<String> {
+        let target = image_target(relative);
+        if self.root.join(&target).is_file() {
+            return Some(target);
+        }
+        let bytes = visiting::read_or_download(&self.storage, &self.url_prefix, relative).await?;
+        self.write(target, bytes).await
+    }
+
+    async fn write(&self, target: String, bytes: Vec<u8>) -> Option<String> {
+        let path = self.root.join(&target);
+        tokio::task::spawn_blocking(move || resize_into(&bytes, &path))
+            .await
+            .ok()?
+            .ok()?;
+        Some(target)
+    }
+}
+
+fn nav(depth: usize, extra: &str) -> String {
+    let up = "../".repeat(depth);
+    format!(
+        "<p><a href=\"{up}index.html\">首页</a> · <a href=\"{up}people.html\">同学们</a>{}</p>",
+        extra
+    )
+}
+
+fn gallery(html: &mut String, photos: &[(String, String)]) {
+    html.push_str("<div class=\"photos\">");
+    for (caption, src) in photos {
+        write!(
+            html,
+            "<figure><a href=\"{src}\"><img src=\"{src}\" alt=\"\" loading=\"lazy\"/></a><figcaption>{}</figcaption></figure>",
+            escape(caption)
+        )
+        .unwrap();
+    }
+    html.push_str("</div>");
+}
+
+fn write_page(root: &Path, relative: &str, title: &str, body: &str) -> std::io::Result<()> {
+    let path = root.join(relative);
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(path, document(title, body))
+}
+
+/// 把整个存档导出成可以离线浏览的静态网站。
+pub async fn export_site(state: State, root: PathBuf) -> std::io::Result<()> {
+    let url_prefix = state
+        .idxtable
+        .get("url_prefix")
+        .unwrap()
+        .as_str()
+        .unwrap()
+        .to_string();
+    let images = Images {
+        root: root.clone(),
+        storage: state.storage.clone(),
+        url_prefix,
+    };
+    let names = names(&state);
+    let profiles = load_profiles(&state, names.len()).await;
+    let queue_table = Arc::new(visiting::read_events(&state).await);
+
+    // 首页：一起的时光和全景
+    let mut index = String::from("<h1>一起的时光</h1>");
+    index.push_str(&nav(0, ""));
+    let together_events = state
+        .idxtable
+        .get("together_event")
+        .unwrap()
+        .as_array()
+        .unwrap();
+    for event in together_events {
+        let description = event
+            .get("description")
+            .and_then(|d| d.as_str())
+            .unwrap_or_default();
+        write!(index, "<h2>{}</h2>", escape(description)).unwrap();
+        let mut photos = vec![];
+        for img in event.get("image").unwrap().as_array().unwrap() {
+            if let Some(src) = images.put(img.as_str().unwrap()).await {
+                photos.push((String::new(), src));
+            }
+        }
+        gallery(&mut index, &photos);
+    }
+    let panoramas = state.idxtable.get("panorama").and_then(|p| p.as_array());
+    if let Some(panoramas) = panoramas {
+        index.push_str("<h1>全景</h1><ul>");
+        for (location, pan) in panoramas.iter().enumerate() {
+            let mut body = nav(1, "");
+            let mut photos = vec![];
+            for name in pan.get("image").unwrap().as_array().unwrap() {
+                let name = name.as_str().unwrap();
+                let relative = format!("/image/panorama/{}", name);
+                if let Some(src) = images.put(&relative).await {
+                    let caption = name.strip_suffix(".jpg").unwrap_or(name).to_string();
+                    photos.push((caption, format!("../{}", src)));
+                }
+            }
+            let title = photos
+                .first()
+                .map(|photo| photo.0.clone())
+                .unwrap_or_else(|| format!("全景 {}", location + 1));
+            write!(body, "<h1>{}</h1>", escape(&title)).unwrap();
+            gallery(&mut body, &photos);
+            write_page(&root, &format!("panorama/{}.html", location), &title, &body)?;
+            write!(
+                index,
+                "<li><a href=\"panorama/{}.html\">{}</a></li>",
+                location,
+                escape(&title)
+            )
+            .unwrap();
+        }
+        index.push_str("</ul>");
+    }
+    write_page(&root, "index.html", "一起的时光", &index)?;
+
+    // 每位同学的资料和时间线
+    let mut people = String::from("<h1>同学们</h1>");
+    people.push_str(&nav(0, ""));
+    people.push_str("<ul>");
+    for (num, name) in names.iter().enumerate().skip(1) {
+        if name.is_empty() {
+            continue;
+        }
+        write!(
+            people,
+            "<li><a href=\"people/{}.html\">{}</a></li>",
+            num,
+            escape(name)
+        )
+        .unwrap();
+        let avatar = images
+            .put(&format!("/image/known_people/{}.jpg", num))
+            .await
+            .map(|src| format!("../{}", src));
+        let mut body = nav(
+            1,
+            &format!(" · <a href=\"../timeline/{}.html\">ta 的时间线</a>", num),
+        );
+        body.push_str(&crate::yearbook::page(
+            name,
+            &names,
+            &profiles[num],
+            avatar,
+            &[],
+        ));
+        write_page(&root, &format!("people/{}.html", num), name, &body)?;
+
+        let mut events = tokio::task::spawn_blocking({
+            let storage = state.storage.clone();
+            let queue_table = queue_table.clone();
+            move || visiting::collect_events(&storage, &queue_table, &visiting::Company::alone(num))
+        })
+        .await?;
+        events.sort_unstable();
+        let mut body = nav(
+            1,
+            &format!(" · <a href=\"../people/{}.html\">ta 的资料</a>", num),
+        );
+        write!(body, "<h1>{} 的时间线</h1>", escape(name)).unwrap();
+        for event in &events {
+            let mut photos = vec![];
+            for experience in &event.experiences {
+                if let Some(src) = images.put(&experience.path).await {
+                    let shot = experience
+                        .shot
+                        .as_ref()
+                        .map(ToString::to_string)
+                        .unwrap_or_default();
+                    photos.push((shot, format!("../{}", src)));
+                }
+            }
+            if photos.is_empty() {
+                continue;
+            }
+            let shot = match &event.experiences.first().unwrap().shot {
+                Some(shot) => shot.to_string(),
+                None => String::from("拍摄时间不详"),
+            };
+            write!(
+                body,
+                "<h2>{}</h2><p class=\"term\">{}</p>",
+                escape(&event.description),
+                shot
+            )
+            .unwrap();
+            gallery(&mut body, &photos);
+        }
+        write_page(
+            &root,
+            &format!("timeline/{}.html", num),
+            &format!("{} 的时间线", name),
+            &body,
+        )?;
+    }
+    people.push_str("</ul>");
+    write_page(&root, "people.html", "同学们", &people)?;
+    Ok(())
+}
+
+/// 在用户选的文件夹里建一个 graduate-site，把首页、全景、资料和时间线都写进去。
+pub async fn export(state: State) -> String {
+    let destination = Destination::Folder {
+        title: "导出网站到...",
+    };
+    export::save_with_notice(destination, |folder| async move {
+        export_site(state, folder.join("graduate-site"))
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok::<_, String>(String::from(
+            "，打开其中 graduate-site 文件夹里的 index.html 即可浏览",
+        ))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_targets_keep_the_original_extension() {
+        assert_eq!(image_target("/image/a.png"), "images/image/a.png.jpg");
+        assert_eq!(image_target("image/a.jpg"), "images/image/a.jpg.jpg");
+        assert_ne!(image_target("a.png"), image_target("a.jpg"));
+    }
+}
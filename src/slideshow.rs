@@ -0,0 +1,221 @@
+use crate::drawing::draw_centered;
+use crate::export::{self, Destination};
+use crate::terms::{self, Term};
+use crate::{visiting, Message, VisitingState};
+use ab_glyph::FontRef;
+use iced::widget::{self, column, row, text};
+use iced::{Alignment, Element};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{imageops, Delay, Frame, Rgba, RgbaImage};
+use tokio::sync::mpsc::{self, Receiver};
+
+/// 可选的动图宽度
+pub const WIDTHS: [Width; 3] = [Width(480), Width(720), Width(1080)];
+/// 每张照片停留的时间
+pub const DURATIONS: [Duration; 4] = [Duration(1), Duration(2), Duration(3), Duration(5)];
+
+const BACKGROUND: Rgba<u8> = Rgba([24, 24, 28, 255]);
+const TITLE_COLOR: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const CAPTION_COLOR: Rgba<u8> = Rgba([120, 158, 204, 255]);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Width(pub u32);
+
+impl std::fmt::Display for Width {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "宽 {} 像素", self.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Duration(pub u32);
+
+impl std::fmt::Display for Duration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "每张 {} 秒", self.0)
+    }
+}
+
+/// 导出时间线动图的设置。
+#[derive(Clone, Debug)]
+pub struct Settings {
+    pub width: Width,
+    pub duration: Duration,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            width: WIDTHS[1],
+            duration: DURATIONS[1],
+        }
+    }
+}
+
+/// 动图中的一帧：照片、事件描述和拍摄时间。
+pub struct Slide {
+    path: String,
+    title: String,
+    caption: String,
+}
+
+pub fn slides(terms: &[Term], displayer: &VisitingState) -> Vec<Slide> {
+    let events = displayer.events.lock().unwrap();
+    let mut slides = vec![];
+    for event in events.iter() {
+        for experience in &event.experiences {
+            let caption = match &experience.shot {
+                Some(shot) => terms::caption(terms, shot),
+                None => String::from("拍摄时间不详"),
+            };
+            slides.push(Slide {
+                path: experience.path.clone(),
+                title: event.description.clone(),
+                caption,
+            });
+        }
+    }
+    slides
+}
+
+/// 照片按比例缩放后居中放在 4:3 的画面上，下方留出写字的位置。
+fn compose(font: &FontRef, photo: &[u8], slide: &Slide, width: u32) -> Option<RgbaImage> {
+    let photo = image::load_from_memory(photo).ok()?;
+    let photo_height = width * 3 / 4;
+    let band = width / 6;
+    let mut canvas = RgbaImage::from_pixel(width, photo_height + band, BACKGROUND);
+    let photo = photo
+        .resize(width, photo_height, imageops::FilterType::Triangle)
+        .to_rgba8();
+    imageops::overlay(
+        &mut canvas,
+        &photo,
+        ((width - photo.width()) / 2) as i64,
+        ((photo_height - photo.height()) / 2) as i64,
+    );
+    let title_size = band as f32 * 0.36;
+    let caption_size = band as f32 * 0.24;
+    let top = photo_height as f32 + band as f32 * 0.12;
+    draw_centered(
+        &mut canvas,
+        font,
+        title_size,
+        &slide.title,
+        top,
+        TITLE_COLOR,
+    );
+    draw_centered(
+        &mut canvas,
+        font,
+        caption_size,
+        &slide.caption,
+        top + title_size * 1.3,
+        CAPTION_COLOR,
+    );
+    Some(canvas)
+}
+
+/// 收到一张合成一张并写进 gif，返回写进去的张数和解码失败的张数。
+fn encode(
+    path: &std::path::Path,
+    mut photos: Receiver<(Slide, Vec<u8>)>,
+    settings: &Settings,
+) -> Result<(usize, usize), String> {
+    let font = FontRef::try_from_slice(crate::FONT).map_err(|e| e.to_string())?;
+    let file = std::fs::File::create(path).map_err(|e| e.to_string())?;
+    let mut encoder = GifEncoder::new_with_speed(file, 10);
+    encoder
+        .set_repeat(Repeat::Infinite)
+        .map_err(|e| e.to_string())?;
+    let mut count = 0;
+    let mut broken = 0;
+    while let Some((slide, bytes)) = photos.blocking_recv() {
+        let Some(canvas) = compose(&font, &bytes, &slide, settings.width.0) else {
+            broken += 1;
+            continue;
+        };
+        let delay = Delay::from_numer_denom_ms(settings.duration.0 * 1000, 1);
+        encoder
+            .encode_frame(Frame::from_parts(canvas, 0, 0, delay))
+            .map_err(|e| e.to_string())?;
+        count += 1;
+    }
+    Ok((count, broken))
+}
+
+/// 把当前时间线上的所有照片导出成一张循环播放的 gif，读不到或解码不了的照片跳过并计数。
+pub async fn export(
+    storage: String,
+    url_prefix: String,
+    name: String,
+    slides: Vec<Slide>,
+    settings: Settings,
+) -> String {
+    let destination = Destination::File {
+        title: "导出时间线动图",
+        name: format!("{}的初中时光.gif", name),
+        filter: ("动图", &["gif"]),
+    };
+    export::save_with_notice(destination, |path| async move {
+        // 一次只读一张交给编码的线程，不把所有照片都留在内存里
+        let (sender, receiver) = mpsc::channel(1);
+        let encoding = tokio::task::spawn_blocking({
+            let path = path.clone();
+            move || encode(&path, receiver, &settings)
+        });
+        let mut unreadable = 0;
+        for slide in slides {
+            let Some(bytes) = visiting::read_or_download(&storage, &url_prefix, &slide.path).await
+            else {
+                unreadable += 1;
+                continue;
+            };
+            // 编码出错时接收端已经没了，后面的不用再读
+            if sender.send((slide, bytes)).await.is_err() {
+                break;
+            }
+        }
+        drop(sender);
+        let (count, broken) = encoding.await.map_err(|e| e.to_string())??;
+        if count == 0 {
+            let _ = std::fs::remove_file(&path);
+            return Err(String::from("没有可以导出的照片"));
+        }
+        let mut detail = format!("，共 {} 张照片", count);
+        if unreadable + broken > 0 {
+            detail.push_str(&format!(
+                "，另有 {} 张读取或解码失败，已跳过",
+                unreadable + broken
+            ));
+        }
+        Ok::<_, String>(detail)
+    })
+    .await
+}
+
+pub fn panel<'a>(settings: &Settings) -> Element<'a, Message> {
+    column![
+        row![
+            widget::pick_list(
+                WIDTHS.to_vec(),
+                Some(settings.width),
+                Message::SlideshowWidth
+            )
+            .text_size(20),
+            widget::pick_list(
+                DURATIONS.to_vec(),
+                Some(settings.duration),
+                Message::SlideshowDuration
+            )
+            .text_size(20),
+        ]
+        .spacing(5),
+        widget::Button::new(text("导出时间线动图").size(30))
+            .padding(10)
+            .style(iced::theme::Button::Secondary)
+            .on_press(Message::ExportSlideshow),
+    ]
+    .spacing(5)
+    .align_items(Alignment::Center)
+    .into()
+}
@@ -0,0 +1,358 @@
+use crate::choosing::Profile;
+use crate::terms::{self, Term};
+use crate::visiting::{self, ShootingTime};
+use crate::{decoding, EntryState, Message, Stage, State};
+use iced::widget::{self, column, container, horizontal_space, row, scrollable, text};
+use iced::{Alignment, Element, Length};
+use std::collections::BTreeMap;
+use time::{Date, Month};
+
+const TOP_PARTNERS: usize = 10;
+const TOP_DAYS: usize = 5;
+
+#[derive(Clone, Debug)]
+pub struct StatisticsState {
+    pub previous_stage: EntryState,
+    /// (编号, 照片数)，多的在前
+    photos_per_person: Vec<(usize, usize)>,
+    /// (编号, 收到的评价数)，多的在前
+    comments_per_person: Vec<(usize, usize)>,
+    /// (编号, 资料里写到的关系数)，多的在前
+    relations_per_person: Vec<(usize, usize)>,
+    /// (编号, 编号, 同框照片数)，多的在前
+    partners: Vec<(usize, usize, usize)>,
+    /// 每个月在各学期里拍了几张照片，按时间先后；跨两个学期的月份分开算
+    months: Vec<(Option<usize>, i32, Month, usize)>,
+    terms: Vec<Term>,
+    busiest_days: Vec<(Date, usize)>,
+    photo_count: usize,
+    undated: usize,
+}
+
+/// 只看 `date` 和已经下载到本地的照片，不会为了统计去下载任何东西。
+fn local_shot_date(img: &toml::Value, storage: &str, path: &str) -> Option<Date> {
+    // 写错了的 `date` 和没写一样看待
+    let declared = img
+        .get("date")
+        .and_then(|date| date.as_datetime())
+        .and_then(ShootingTime::parse);
+    let shot = match declared {
+        Some(shot) => shot,
+        None => decoding::shooting_time_of_file(&format!("{}{}", storage, path))?,
+    };
+    Some(shot.date())
+}
+
+/// 已经下载到本地的资料，没下载的当作空的，下标为编号。
+fn local_profiles(storage: &str, count: usize, terms: &[Term]) -> Vec<Profile> {
+    let mut profiles = vec![Profile::default()];
+    for num in 1..count {
+        let text = std::fs::read_to_string(format!("{}/profile/{}.toml", storage, num));
+        profiles.push(
+            text.ok()
+                .and_then(|text| Profile::parse(num, &text, count, terms))
+                .unwrap_or_default(),
+        );
+    }
+    profiles
+}
+
+/// (编号, 数量)，多的在前，一样多的编号小的在前，数量为零的不要。
+fn ranked(counts: impl IntoIterator<Item = (usize, usize)>) -> Vec<(usize, usize)> {
+    let mut ranked: Vec<(usize, usize)> = counts.into_iter().filter(|&(_, n)| n > 0).collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    ranked
+}
+
+fn count(
+    queue_table: &toml::Table,
+    profiles: &[Profile],
+    storage: &str,
+    terms: Vec<Term>,
+    previous_stage: EntryState,
+) -> StatisticsState {
+    let mut photos: BTreeMap<usize, usize> = BTreeMap::new();
+    let mut pairs: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    let mut months: BTreeMap<(i32, u8, Option<usize>), usize> = BTreeMap::new();
+    let mut days: BTreeMap<Date, usize> = BTreeMap::new();
+    let mut photo_count = 0;
+    let mut undated = 0;
+    for kind in ["experience", "event"] {
+        for event in queue_table.get(kind).unwrap().as_array().unwrap() {
+            for img in event.get("image").unwrap().as_array().unwrap() {
+                photo_count += 1;
+                let mut path = img.get("path").unwrap().as_str().unwrap().to_string();
+                if kind == "experience" && img.get("with").is_some() {
+                    path = format!("image/experience/{}", path);
+                }
+                match local_shot_date(img, storage, &path) {
+                    Some(date) => {
+                        *days.entry(date).or_default() += 1;
+                        let term = terms::term_of(&terms, date);
+                        *months
+                            .entry((date.year(), date.month() as u8, term))
+                            .or_default() += 1;
+                    }
+                    None => undated += 1,
+                }
+                let Some(with) = img.get("with").and_then(|with| with.as_array()) else {
+                    continue;
+                };
+                let mut people: Vec<usize> = with
+                    .iter()
+                    .filter_map(|person| person.as_integer())
+                    .map(|person| person as usize)
+                    .collect();
+                people.sort_unstable();
+                people.dedup();
+                for (index, &a) in people.iter().enumerate() {
+                    *photos.entry(a).or_default() += 1;
+                    for &b in &people[index + 1..] {
+                        *pairs.entry((a, b)).or_default() += 1;
+                    }
+                }
+            }
+        }
+    }
+    let photos_per_person = ranked(photos);
+    let comments_per_person = ranked(
+        profiles
+            .iter()
+            .enumerate()
+            .map(|(num, profile)| (num, profile.comment.len())),
+    );
+    let relations_per_person = ranked(
+        profiles
+            .iter()
+            .enumerate()
+            .map(|(num, profile)| (num, profile.relationship.len())),
+    );
+    let mut partners: Vec<(usize, usize, usize)> =
+        pairs.into_iter().map(|((a, b), n)| (a, b, n)).collect();
+    partners.sort_by(|a, b| b.2.cmp(&a.2).then((a.0, a.1).cmp(&(b.0, b.1))));
+    partners.truncate(TOP_PARTNERS);
+    let mut busiest_days: Vec<(Date, usize)> = days.into_iter().collect();
+    busiest_days.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    busiest_days.truncate(TOP_DAYS);
+    StatisticsState {
+        previous_stage,
+        photos_per_person,
+        comments_per_person,
+        relations_per_person,
+        partners,
+        months: months
+            .into_iter()
+            .map(|((year, month, term), n)| (term, year, Month::try_from(month).unwrap(), n))
+            .collect(),
+        terms,
+        busiest_days,
+        photo_count,
+        undated,
+    }
+}
+
+pub async fn load_statistics(state: State) -> Result<State, crate::Error> {
+    let previous_stage = match state.stage {
+        Stage::EntryEvents(ref previous) => previous.clone(),
+        _ => return Err(crate::Error::APIError),
+    };
+    let queue_table = visiting::read_events(&state).await;
+    let storage = state.storage.clone();
+    let terms = state.terms.clone();
+    let people = crate::site::names(&state).len();
+    let statistics = tokio::task::spawn_blocking(move || {
+        let profiles = local_profiles(&storage, people, &terms);
+        count(&queue_table, &profiles, &storage, terms, previous_stage)
+    })
+    .await?;
+    Ok(State {
+        stage: Stage::Statistics(statistics),
+        ..state
+    })
+}
+
+fn bar<'a>(label: String, value: usize, max: usize) -> Element<'a, Message> {
+    row![
+        text(label).size(22).width(Length::Fixed(180.0)),
+        widget::progress_bar(0.0..=max.max(1) as f32, value as f32)
+            .height(Length::Fixed(18.0))
+            .width(Length::Fill),
+        text(value).size(22).width(Length::Fixed(60.0)),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center)
+    .into()
+}
+
+fn section<'a>(title: &str, content: Element<'a, Message>) -> Element<'a, Message> {
+    column![
+        text(title).size(40),
+        row![horizontal_space(Length::Fixed(20.0)), content]
+    ]
+    .spacing(5)
+    .into()
+}
+
+pub fn show_statistics<'a>(state: &State, statistics: &StatisticsState) -> Element<'a, Message> {
+    let mut photos = column![].spacing(3);
+    let most_photos = statistics.photos_per_person.first().map_or(0, |p| p.1);
+    for &(person, n) in &statistics.photos_per_person {
+        photos = photos.push(bar(state.get_name(person), n, most_photos));
+    }
+    let mut comments = column![].spacing(3);
+    let most_comments = statistics.comments_per_person.first().map_or(0, |p| p.1);
+    for &(person, n) in &statistics.comments_per_person {
+        comments = comments.push(bar(state.get_name(person), n, most_comments));
+    }
+    let mut relations = column![].spacing(3);
+    let most_relations = statistics.relations_per_person.first().map_or(0, |p| p.1);
+    for &(person, n) in &statistics.relations_per_person {
+        relations = relations.push(bar(state.get_name(person), n, most_relations));
+    }
+    let mut partners = column![].spacing(3);
+    let most_partnered = statistics.partners.first().map_or(0, |p| p.2);
+    for &(a, b, n) in &statistics.partners {
+        partners = partners.push(bar(
+            format!("{} & {}", state.get_name(a), state.get_name(b)),
+            n,
+            most_partnered,
+        ));
+    }
+    let mut months = column![].spacing(3);
+    let busiest_month = statistics.months.iter().map(|m| m.3).max().unwrap_or(0);
+    // 每进入一个新学期先写上学期名
+    let mut current_term = None;
+    for &(term, year, month, n) in &statistics.months {
+        if current_term != Some(term) {
+            months = months.push(
+                text(match term {
+                    Some(term) => statistics.terms[term].name.as_str(),
+                    None => "其他时间",
+                })
+                .size(26),
+            );
+            current_term = Some(term);
+        }
+        months = months.push(bar(
+            format!("{} 年 {} 月", year, month as u8),
+            n,
+            busiest_month,
+        ));
+    }
+    let mut days = column![].spacing(3);
+    for (date, n) in &statistics.busiest_days {
+        days = days.push(text(format!("{}：{} 张", date, n)).size(26));
+    }
+    let content = column![
+        row![
+            widget::Button::new(text("返回").size(28))
+                .style(iced::theme::Button::Secondary)
+                .on_press(Message::BackStage)
+                .padding(15),
+            text("班级统计").size(50),
+        ]
+        .spacing(20)
+        .align_items(Alignment::Center),
+        text(format!(
+            "共 {} 张照片，其中 {} 张暂时不知道拍摄日期",
+            statistics.photo_count, statistics.undated
+        ))
+        .size(26),
+        section("每个人的照片数", photos.into()),
+        section("最常同框的搭档", partners.into()),
+        section("收到的评价数", comments.into()),
+        section("资料里写到的关系", relations.into()),
+        section("每个月的照片数", months.into()),
+        section("最热闹的日子", days.into()),
+    ]
+    .spacing(20);
+    container(scrollable(row![
+        horizontal_space(Length::FillPortion(1)),
+        content.width(Length::FillPortion(18)),
+        horizontal_space(Length::FillPortion(1)),
+    ]))
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::choosing::{Comment, Relationship};
+
+    fn comment(from: usize) -> Comment {
+        Comment {
+            from,
+            description: String::from("很好"),
+            date: None,
+        }
+    }
+
+    #[test]
+    fn count_aggregates_photos_comments_and_relations() {
+        // (events.toml, 每人照片数, 搭档, 没有日期的照片数)
+        let cases: [(&str, Vec<(usize, usize)>, Vec<(usize, usize, usize)>, usize); 3] = [
+            ("experience = []\nevent = []", vec![], vec![], 0),
+            (
+                r#"
+                experience = []
+                [[event]]
+                description = "春游"
+                image = [
+                    { path = "1.jpg", date = 2021-04-01, with = [1, 2] },
+                    { path = "2.jpg", date = 2021-04-01, with = [2, 3, 2] },
+                    { path = "3.jpg" },
+                ]
+                "#,
+                vec![(2, 2), (1, 1), (3, 1)],
+                vec![(1, 2, 1), (2, 3, 1)],
+                1,
+            ),
+            (
+                r#"
+                event = []
+                [[experience]]
+                description = "运动会"
+                image = [
+                    { path = "4.jpg", date = 2021-10-01, with = [1, 3] },
+                    { path = "5.jpg", date = 2021-10-02, with = [3, 1] },
+                ]
+                "#,
+                vec![(1, 2), (3, 2)],
+                vec![(1, 3, 2)],
+                0,
+            ),
+        ];
+        let profiles = vec![
+            Profile::default(),
+            Profile {
+                comment: vec![comment(2), comment(3)],
+                ..Default::default()
+            },
+            Profile {
+                relationship: vec![Relationship {
+                    by: 1,
+                    is: String::from("同桌"),
+                }],
+                ..Default::default()
+            },
+            Profile::default(),
+        ];
+        for (events, photos, partners, undated) in cases {
+            let queue_table: toml::Table = events.parse().unwrap();
+            let statistics = count(
+                &queue_table,
+                &profiles,
+                "/nonexistent",
+                vec![],
+                EntryState::default(),
+            );
+            assert_eq!(statistics.photos_per_person, photos, "{}", events);
+            assert_eq!(statistics.partners, partners, "{}", events);
+            assert_eq!(statistics.undated, undated, "{}", events);
+            assert_eq!(statistics.comments_per_person, [(1, 2)]);
+            assert_eq!(statistics.relations_per_person, [(2, 1)]);
+        }
+    }
+}
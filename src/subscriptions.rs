@@ -1,4 +1,4 @@
-use crate::Message;
+use crate::{imageviewer, Message};
 use iced::keyboard::{self, KeyCode};
 use iced::Event;
 
@@ -11,12 +11,29 @@ macro_rules! with_key {
     };
 }
 
-fn global_response(event: keyboard::Event) -> Option<Message> {
+/// 带 Ctrl/Alt 的组合键和 Esc，输入框有焦点时也照常响应。
+fn chord_response(event: keyboard::Event) -> Option<Message> {
     match event {
         keyboard::Event::KeyPressed {
             modifiers: m,
             key_code,
         } => match key_code {
+            KeyCode::F if m.command() => Some(Message::OpenSearch),
+            KeyCode::Escape => Some(Message::EscapeFullScreen),
+            KeyCode::Enter if m.alt() => Some(Message::ToggleMode),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// 单个按键的全局快捷键。
+fn global_response(event: keyboard::Event) -> Option<Message> {
+    match event {
+        keyboard::Event::KeyPressed {
+            modifiers: m,
+            key_code,
+        } if !m.command() && !m.alt() => match key_code {
             KeyCode::Plus | KeyCode::NumpadAdd => Some(Message::ScaleEnlarge),
             KeyCode::Minus | KeyCode::NumpadSubtract => Some(Message::ScaleDown),
             KeyCode::Equals | KeyCode::NumpadEquals => Some(Message::ScaleRestore),
@@ -25,127 +42,129 @@ fn global_response(event: keyboard::Event) -> Option<Message> {
             KeyCode::N => Some(Message::NextSong),
             KeyCode::R => Some(Message::Refresh),
             KeyCode::E => Some(Message::OpenSettings),
-            KeyCode::Escape => Some(Message::EscapeFullScreen),
-            KeyCode::Enter => {
-                if m.alt() {
-                    Some(Message::ToggleMode)
-                } else {
-                    None
-                }
-            }
             _ => None,
         },
         _ => None,
     }
 }
 
-pub fn on_loading(event: Event, _: iced::event::Status) -> Option<Message> {
+/// 获得焦点的图片查看器自己处理了这个按键（缩放、平移、适应方式），就不再当作快捷键。
+fn taken_by_viewer(event: &Event, status: iced::event::Status) -> bool {
+    if status != iced::event::Status::Captured {
+        return false;
+    }
     match event {
-        Event::Keyboard(keyboard_event) => {
-            if let Some(ret) = global_response(keyboard_event) {
-                Some(ret)
-            } else {
-                None
-            }
-        }
-        _ => None,
+        Event::Keyboard(keyboard::Event::KeyPressed {
+            key_code,
+            modifiers,
+        }) => !modifiers.command() && imageviewer::handles(*key_code),
+        _ => false,
     }
 }
 
-pub fn on_entry_state(event: Event, _: iced::event::Status) -> Option<Message> {
-    match event {
-        Event::Keyboard(keyboard_event) => {
-            if let Some(ret) = global_response(keyboard_event) {
-                return Some(ret);
-            }
-            match keyboard_event {
-                with_key!(KeyCode::Enter) => Some(Message::NextStage),
-                with_key!(KeyCode::Left) | with_key!(KeyCode::A) => Some(Message::PreviousEvent),
-                with_key!(KeyCode::Right) | with_key!(KeyCode::D) => Some(Message::NextEvent),
-                with_key!(KeyCode::Up) | with_key!(KeyCode::W) => Some(Message::PreviousPhoto),
-                with_key!(KeyCode::Down) | with_key!(KeyCode::S) => Some(Message::NextPhoto),
-                with_key!(KeyCode::Space) => Some(Message::NextEvent),
-                keyboard::Event::KeyPressed {
-                    key_code: KeyCode::Tab,
-                    modifiers,
-                } => Some(if modifiers.shift() {
-                    Message::PreviousEvent
-                } else {
-                    Message::NextEvent
-                }),
-                _ => None,
-            }
-        }
-        _ => None,
+/// 各阶段共用的按键处理：先看组合键，再看全局快捷键，最后交给 `stage`。
+/// 按键被别的控件（比如正在打字的输入框）捕获时只响应组合键。
+fn respond(
+    event: Event,
+    status: iced::event::Status,
+    stage: fn(keyboard::Event) -> Option<Message>,
+) -> Option<Message> {
+    if taken_by_viewer(&event, status) {
+        return None;
+    }
+    let Event::Keyboard(keyboard_event) = event else {
+        return None;
+    };
+    if let Some(ret) = chord_response(keyboard_event) {
+        return Some(ret);
     }
+    if status == iced::event::Status::Captured {
+        return None;
+    }
+    global_response(keyboard_event).or_else(|| stage(keyboard_event))
+}
+
+pub fn on_loading(event: Event, status: iced::event::Status) -> Option<Message> {
+    respond(event, status, |_| None)
 }
 
-pub fn on_choosing_character(event: Event, _: iced::event::Status) -> Option<Message> {
+/// 搜索框打开时在里面打字，不能触发任何单键快捷键。
+pub fn on_search(event: Event, _: iced::event::Status) -> Option<Message> {
     match event {
-        Event::Keyboard(keyboard_event) => {
-            if let Some(ret) = global_response(keyboard_event) {
-                return Some(ret);
-            }
-            match keyboard_event {
-                with_key!(KeyCode::Left) => Some(Message::PreviousPerson),
-                with_key!(KeyCode::Right) => Some(Message::NextPerson),
-                with_key!(KeyCode::Space) => Some(Message::NextStage),
-                keyboard::Event::KeyPressed {
-                    key_code: KeyCode::Tab,
-                    modifiers,
-                } => Some(if modifiers.shift() {
-                    Message::PreviousPerson
-                } else {
-                    Message::NextPerson
-                }),
-                _ => None,
-            }
-        }
+        Event::Keyboard(keyboard_event) => chord_response(keyboard_event),
         _ => None,
     }
 }
 
-pub fn on_graduation(event: Event, _: iced::event::Status) -> Option<Message> {
-    match event {
-        Event::Keyboard(keyboard_event) => {
-            if let Some(ret) = global_response(keyboard_event) {
-                return Some(ret);
-            }
-            match keyboard_event {
-                with_key!(KeyCode::Space) => Some(Message::TogglePanelShown),
-                _ => None,
-            }
-        }
+pub fn on_entry_state(event: Event, status: iced::event::Status) -> Option<Message> {
+    respond(event, status, |keyboard_event| match keyboard_event {
+        with_key!(KeyCode::Enter) => Some(Message::NextStage),
+        with_key!(KeyCode::Left) | with_key!(KeyCode::A) => Some(Message::PreviousEvent),
+        with_key!(KeyCode::Right) | with_key!(KeyCode::D) => Some(Message::NextEvent),
+        with_key!(KeyCode::Up) | with_key!(KeyCode::W) => Some(Message::PreviousPhoto),
+        with_key!(KeyCode::Down) | with_key!(KeyCode::S) => Some(Message::NextPhoto),
+        with_key!(KeyCode::Space) => Some(Message::NextEvent),
+        keyboard::Event::KeyPressed {
+            key_code: KeyCode::Tab,
+            modifiers,
+        } => Some(if modifiers.shift() {
+            Message::PreviousEvent
+        } else {
+            Message::NextEvent
+        }),
         _ => None,
-    }
+    })
 }
 
-pub fn on_showing_plots(event: Event, _: iced::event::Status) -> Option<Message> {
-    match event {
-        Event::Keyboard(keyboard_event) => {
-            if let Some(ret) = global_response(keyboard_event) {
-                return Some(ret);
-            }
-            match keyboard_event {
-                with_key!(KeyCode::Enter) => Some(Message::NextStage),
-                with_key!(KeyCode::Left) | with_key!(KeyCode::A) => Some(Message::PreviousEvent),
-                with_key!(KeyCode::Right) | with_key!(KeyCode::D) => Some(Message::NextEvent),
-                with_key!(KeyCode::Up) | with_key!(KeyCode::W) => Some(Message::PreviousPhoto),
-                with_key!(KeyCode::Down) | with_key!(KeyCode::S) => Some(Message::NextPhoto),
-                with_key!(KeyCode::Space) => Some(Message::NextEvent),
-                keyboard::Event::KeyPressed {
-                    key_code: KeyCode::Tab,
-                    modifiers,
-                } => Some(if modifiers.shift() {
-                    Message::PreviousEvent
-                } else {
-                    Message::NextEvent
-                }),
-                _ => None,
-            }
-        }
+pub fn on_choosing_character(event: Event, status: iced::event::Status) -> Option<Message> {
+    respond(event, status, |keyboard_event| match keyboard_event {
+        with_key!(KeyCode::Left) => Some(Message::PreviousPerson),
+        with_key!(KeyCode::Right) => Some(Message::NextPerson),
+        with_key!(KeyCode::Space) => Some(Message::NextStage),
+        keyboard::Event::KeyPressed {
+            key_code: KeyCode::Tab,
+            modifiers,
+        } => Some(if modifiers.shift() {
+            Message::PreviousPerson
+        } else {
+            Message::NextPerson
+        }),
         _ => None,
-    }
+    })
+}
+
+pub fn on_graduation(event: Event, status: iced::event::Status) -> Option<Message> {
+    respond(event, status, |keyboard_event| match keyboard_event {
+        with_key!(KeyCode::Space) => Some(Message::TogglePanelShown),
+        _ => None,
+    })
+}
+
+pub fn on_statistics(event: Event, status: iced::event::Status) -> Option<Message> {
+    respond(event, status, |keyboard_event| match keyboard_event {
+        with_key!(KeyCode::Backspace) => Some(Message::BackStage),
+        _ => None,
+    })
+}
+
+pub fn on_showing_plots(event: Event, status: iced::event::Status) -> Option<Message> {
+    respond(event, status, |keyboard_event| match keyboard_event {
+        with_key!(KeyCode::Enter) => Some(Message::NextStage),
+        with_key!(KeyCode::Left) | with_key!(KeyCode::A) => Some(Message::PreviousEvent),
+        with_key!(KeyCode::Right) | with_key!(KeyCode::D) => Some(Message::NextEvent),
+        with_key!(KeyCode::Up) | with_key!(KeyCode::W) => Some(Message::PreviousPhoto),
+        with_key!(KeyCode::Down) | with_key!(KeyCode::S) => Some(Message::NextPhoto),
+        with_key!(KeyCode::Space) => Some(Message::NextEvent),
+        keyboard::Event::KeyPressed {
+            key_code: KeyCode::Tab,
+            modifiers,
+        } => Some(if modifiers.shift() {
+            Message::PreviousEvent
+        } else {
+            Message::NextEvent
+        }),
+        _ => None,
+    })
 }
 
 #[cfg(target_os = "windows")]
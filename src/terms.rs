@@ -0,0 +1,137 @@
+use crate::visiting::ShootingTime;
+use time::{Date, Month};
+use toml::value::Table;
+
+/// 一个学期，`start` 和 `end` 都包含在内。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Term {
+    pub name: String,
+    pub start: Date,
+    pub end: Date,
+}
+
+fn to_date(value: &toml::Value) -> Option<Date> {
+    let date = value.as_datetime()?.date?;
+    Date::from_calendar_date(
+        date.year as i32,
+        Month::try_from(date.month).ok()?,
+        date.day,
+    )
+    .ok()
+}
+
+/// 读取 index.toml 中的 `[[term]]`，按开始日期排序；只在载入 index.toml 时读一次，存在 `State` 里。
+pub fn read_terms(idxtable: &Table, warnings: &mut Vec<String>) -> Vec<Term> {
+    let Some(array) = idxtable.get("term").and_then(|term| term.as_array()) else {
+        warnings.push(String::from(
+            "index.toml: 没有 `[[term]]`，照片和评语都不会标出学期",
+        ));
+        return vec![];
+    };
+    let mut terms = vec![];
+    for term in array {
+        let name = term.get("name").and_then(|name| name.as_str());
+        let start = term.get("start").and_then(to_date);
+        let end = term.get("end").and_then(to_date);
+        match (name, start, end) {
+            (Some(name), Some(start), Some(end)) if start <= end => terms.push(Term {
+                name: name.to_string(),
+                start,
+                end,
+            }),
+            _ => warnings.push(format!("index.toml: 跳过格式不对的 `term`：{}", term)),
+        }
+    }
+    terms.sort_by_key(|term| term.start);
+    terms
+}
+
+/// 这一天属于第几个学期。
+pub fn term_of(terms: &[Term], date: Date) -> Option<usize> {
+    terms
+        .iter()
+        .position(|term| term.start <= date && date <= term.end)
+}
+
+pub fn name_of(terms: &[Term], date: Date) -> Option<&str> {
+    Some(terms[term_of(terms, date)?].name.as_str())
+}
+
+/// 「拍摄时间（学期）」，不在任何学期里的只写拍摄时间。
+pub fn caption(terms: &[Term], shot: &ShootingTime) -> String {
+    match name_of(terms, shot.date()) {
+        Some(term) => format!("{}（{}）", shot, term),
+        None => shot.to_string(),
+    }
+}
+
+/// 有 `flag` 标记的同学，写在 index.toml 的 `[flags]` 里，如 `no_photos = [38, 39]`；
+/// 没有写这个标记时返回 `None`，由调用的地方自己推断。
+pub fn flagged(idxtable: &Table, flag: &str) -> Option<Vec<usize>> {
+    let people = idxtable.get("flags")?.get(flag)?.as_array()?;
+    Some(
+        people
+            .iter()
+            .filter_map(|person| person.as_integer())
+            .map(|person| person as usize)
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    fn idxtable() -> Table {
+        toml::from_str(
+            r#"
+            [[term]]
+            name = "七年级下学期"
+            start = 2021-02-01
+            end = 2021-08-31
+
+            [[term]]
+            name = "七年级上学期"
+            start = 2020-09-01
+            end = 2021-01-31
+
+            [[term]]
+            name = "写反了"
+            start = 2022-01-01
+            end = 2021-01-01
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn read_terms_sorts_and_skips_malformed() {
+        let mut warnings = vec![];
+        let terms = read_terms(&idxtable(), &mut warnings);
+        let names: Vec<&str> = terms.iter().map(|term| term.name.as_str()).collect();
+        assert_eq!(names, ["七年级上学期", "七年级下学期"]);
+        assert_eq!(warnings.len(), 1);
+
+        let mut warnings = vec![];
+        assert!(read_terms(&Table::new(), &mut warnings).is_empty());
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn term_of_includes_both_ends() {
+        let terms = read_terms(&idxtable(), &mut vec![]);
+        assert_eq!(term_of(&terms, date!(2020 - 09 - 01)), Some(0));
+        assert_eq!(term_of(&terms, date!(2021 - 01 - 31)), Some(0));
+        assert_eq!(term_of(&terms, date!(2021 - 02 - 01)), Some(1));
+        assert_eq!(term_of(&terms, date!(2020 - 08 - 31)), None);
+    }
+
+    #[test]
+    fn name_of_looks_up_the_term() {
+        let terms = read_terms(&idxtable(), &mut vec![]);
+        assert_eq!(name_of(&terms, date!(2021 - 05 - 04)), Some("七年级下学期"));
+        assert_eq!(name_of(&terms, date!(2023 - 01 - 01)), None);
+        assert_eq!(name_of(&[], date!(2021 - 05 - 04)), None);
+    }
+}
@@ -0,0 +1,21 @@
+use std::sync::Mutex;
+
+/// 读取内容时发现的问题，在设置里给内容作者看，同样的提醒只记一次。
+static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+pub fn warn(message: String) {
+    let mut warnings = WARNINGS.lock().unwrap();
+    if !warnings.contains(&message) {
+        warnings.push(message);
+    }
+}
+
+pub fn report(messages: &[String]) {
+    for message in messages {
+        warn(message.clone());
+    }
+}
+
+pub fn warnings() -> Vec<String> {
+    WARNINGS.lock().unwrap().clone()
+}
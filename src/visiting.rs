@@ -1,4 +1,4 @@
-use crate::{Memories, Stage, State};
+use crate::{decoding, validation, Memories, Stage, State};
 use iced::widget::image;
 use rand::prelude::SliceRandom;
 use serde::{Deserialize, Serialize};
@@ -29,20 +29,10 @@ impl PartialOrd for Event {
 }
 impl Ord for Event {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        use time::macros::time;
-        let cmp_a = match self.experiences.first().unwrap().shot {
-            ShootingTime::Approximate(approximate) => {
-                PrimitiveDateTime::new(approximate, time!(0:00))
-            }
-            ShootingTime::Precise(precise) => precise.clone(),
-        };
-        let cmp_b = match other.experiences.first().unwrap().shot {
-            ShootingTime::Approximate(approximate) => {
-                PrimitiveDateTime::new(approximate, time!(0:00))
-            }
-            ShootingTime::Precise(precise) => precise.clone(),
-        };
-        cmp_a.cmp(&cmp_b)
+        self.experiences
+            .first()
+            .unwrap()
+            .cmp(other.experiences.first().unwrap())
     }
 }
 
@@ -57,10 +47,60 @@ impl Event {
 
 #[derive(Clone, Debug)]
 pub struct Experience {
-    pub shot: ShootingTime,
+    /// 既没有手写 `date`、本地也读不到 EXIF 时为 `None`
+    pub shot: Option<ShootingTime>,
     pub path: String,
     pub handle: Option<image::Handle>,
+    pub animation: Option<decoding::Animation>,
+    /// 下载或解码失败的原因，有的话就不再显示「加载中」
+    pub failed: Option<String>,
     pub join_handle: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
+    /// 照片里有哪些人，为空表示这是大家的合照
+    pub with: Vec<usize>,
+    pub regions: Vec<Region>,
+}
+
+/// 照片中某个人所在的区域，坐标都是相对于整张照片的比例。
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Region {
+    pub who: usize,
+    pub rect: iced::Rectangle,
+}
+
+/// 读取 `regions = [{ who = 3, rect = [x, y, width, height] }]`，格式不对的直接忽略。
+fn parse_regions(img: &toml::Value) -> Vec<Region> {
+    let mut regions = vec![];
+    let Some(array) = img.get("regions").and_then(|regions| regions.as_array()) else {
+        return regions;
+    };
+    for region in array {
+        let who = region.get("who").and_then(|who| who.as_integer());
+        let rect = region.get("rect").and_then(|rect| rect.as_array());
+        let (Some(who), Some(rect)) = (who, rect) else {
+            continue;
+        };
+        let values: Vec<f32> = rect
+            .iter()
+            .filter_map(|value| {
+                value
+                    .as_float()
+                    .or_else(|| value.as_integer().map(|integer| integer as f64))
+            })
+            .map(|value| value as f32)
+            .collect();
+        if let [x, y, width, height] = values[..] {
+            regions.push(Region {
+                who: who as usize,
+                rect: iced::Rectangle {
+                    x,
+                    y,
+                    width,
+                    height,
+                },
+            });
+        }
+    }
+    regions
 }
 
 impl PartialEq for Experience {
@@ -76,20 +116,10 @@ impl PartialOrd for Experience {
 }
 impl Ord for Experience {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        use time::macros::time;
-        let cmp_a = match self.shot {
-            ShootingTime::Approximate(approximate) => {
-                PrimitiveDateTime::new(approximate, time!(0:00))
-            }
-            ShootingTime::Precise(precise) => precise.clone(),
-        };
-        let cmp_b = match other.shot {
-            ShootingTime::Approximate(approximate) => {
-                PrimitiveDateTime::new(approximate, time!(0:00))
-            }
-            ShootingTime::Precise(precise) => precise.clone(),
-        };
-        cmp_a.cmp(&cmp_b)
+        // 没有拍摄时间的排在最后
+        let key =
+            |shot: &Option<ShootingTime>| (shot.is_none(), shot.as_ref().map(ShootingTime::moment));
+        key(&self.shot).cmp(&key(&other.shot))
     }
 }
 
@@ -144,6 +174,36 @@ impl Into<toml::value::Datetime> for ShootingTime {
         }
     }
 }
+impl ShootingTime {
+    /// 与 `From` 相同，但带时区或者只有时间的写法返回 `None` 而不是崩溃。
+    pub fn parse(item: &toml::value::Datetime) -> Option<Self> {
+        let text = item.to_string();
+        Date::parse(&text, &APPROXIMATE_FORMAT)
+            .map(ShootingTime::Approximate)
+            .or_else(|_| {
+                PrimitiveDateTime::parse(&text, &PRECISE_FORMAT).map(ShootingTime::Precise)
+            })
+            .ok()
+    }
+
+    pub fn date(&self) -> Date {
+        match self {
+            ShootingTime::Approximate(approximate) => *approximate,
+            ShootingTime::Precise(precise) => precise.date(),
+        }
+    }
+
+    /// 排序用的时刻，只知道日期的算作当天零点。
+    pub fn moment(&self) -> PrimitiveDateTime {
+        match self {
+            ShootingTime::Approximate(approximate) => {
+                PrimitiveDateTime::new(*approximate, time::Time::MIDNIGHT)
+            }
+            ShootingTime::Precise(precise) => *precise,
+        }
+    }
+}
+
 impl std::fmt::Display for ShootingTime {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -160,72 +220,212 @@ impl std::fmt::Display for ShootingTime {
     }
 }
 
-pub async fn get_queue(state: State) -> Result<State, crate::Error> {
-    let events_path = std::path::Path::new(&format!("{}/events.toml", &state.storage)).to_owned();
-    let queue_table = {
-        if events_path.is_file() {
-            let events_text = fs::read_to_string(&events_path).unwrap();
-            toml::Table::from_str(events_text.as_str()).unwrap()
+/// 照片的拍摄时间：优先使用 events.toml 中的 `date`，缺省时读取本地照片的 EXIF，不会为此去下载。
+fn shot_time(img: &toml::Value, storage: &str, path: &str) -> Option<ShootingTime> {
+    let recorded = decoding::shooting_time_of_file(&format!("{}{}", storage, path));
+    let declared = img
+        .get("date")
+        .and_then(|date| date.as_datetime())
+        .and_then(ShootingTime::parse);
+    match declared {
+        Some(declared) => {
+            if let Some(recorded) = recorded {
+                validate_shooting_time(path, &declared, &recorded);
+            }
+            Some(declared)
+        }
+        None => recorded,
+    }
+}
+
+/// 内容校验：手写的日期与 EXIF 记录的不是同一天时提醒内容作者。
+pub fn validate_shooting_time(path: &str, declared: &ShootingTime, recorded: &ShootingTime) {
+    if declared.date() != recorded.date() {
+        validation::warn(format!(
+            "events.toml: `{}` 的 date 为 {}，但照片 EXIF 记录的拍摄时间为 {}",
+            path, declared, recorded
+        ));
+    }
+}
+
+/// 要看谁的经历：一个人，或者几位同学共同的回忆。
+#[derive(Clone, Debug, Default)]
+pub struct Company {
+    pub people: Vec<usize>,
+    /// 为真时照片里要有所有人，否则有其中任意一人即可
+    pub require_all: bool,
+}
+
+impl Company {
+    pub fn alone(person: usize) -> Self {
+        Company {
+            people: vec![person],
+            require_all: true,
+        }
+    }
+
+    pub fn matches(&self, with: &[usize]) -> bool {
+        if self.require_all {
+            self.people.iter().all(|person| with.contains(person))
         } else {
-            let events_url = format!(
-                "{}/events.toml",
-                state.idxtable.get("url_prefix").unwrap().as_str().unwrap()
-            );
-            let events_text = reqwest::get(events_url)
-                .await
-                .unwrap()
-                .text()
-                .await
-                .unwrap();
-            let mut events_file = std::fs::File::create(&events_path).unwrap();
-            events_file.write_all(&events_text.as_bytes()).unwrap();
-            toml::Table::from_str(events_text.as_str()).unwrap()
+            self.people.iter().any(|person| with.contains(person))
         }
-    };
-    let mut queue_event = Vec::<Event>::with_capacity(queue_table.len());
-    let (chose_person, character_name) = match state.stage {
-        Stage::ChoosingCharacter(ref choosing) => {
-            let on = choosing.on_character.unwrap();
-            (on, choosing.avatars[on].name.clone())
+    }
+}
+
+/// 读取本地的 events.toml，没有的话先下载下来。
+pub async fn read_events(state: &State) -> toml::Table {
+    let events_path = std::path::Path::new(&format!("{}/events.toml", &state.storage)).to_owned();
+    if events_path.is_file() {
+        let events_text = fs::read_to_string(&events_path).unwrap();
+        toml::Table::from_str(events_text.as_str()).unwrap()
+    } else {
+        let events_url = format!(
+            "{}/events.toml",
+            state.idxtable.get("url_prefix").unwrap().as_str().unwrap()
+        );
+        let events_text = reqwest::get(events_url)
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        let mut events_file = std::fs::File::create(&events_path).unwrap();
+        events_file.write_all(&events_text.as_bytes()).unwrap();
+        toml::Table::from_str(events_text.as_str()).unwrap()
+    }
+}
+
+fn with_of(img: &toml::Value) -> Option<Vec<usize>> {
+    let with_people = img.get("with")?.as_array()?;
+    let mut with = Vec::with_capacity(with_people.len());
+    for i in with_people {
+        with.push(i.as_integer().unwrap() as usize);
+    }
+    Some(with)
+}
+
+/// 一张有某人在的照片。
+#[derive(Clone, Debug)]
+pub struct Photo {
+    pub description: String,
+    pub path: String,
+    pub date: Option<toml::value::Datetime>,
+}
+
+/// `person` 出现过的所有照片，路径与 [`collect_events`] 中的一致。
+pub fn photos_of(queue_table: &toml::Table, person: usize) -> Vec<Photo> {
+    let mut photos = vec![];
+    for kind in ["experience", "event"] {
+        for event in queue_table.get(kind).unwrap().as_array().unwrap() {
+            let description = event.get("description").unwrap().as_str().unwrap();
+            for img in event.get("image").unwrap().as_array().unwrap() {
+                let Some(with) = with_of(img) else {
+                    continue;
+                };
+                if !with.contains(&person) {
+                    continue;
+                }
+                let path = img.get("path").unwrap().as_str().unwrap();
+                photos.push(Photo {
+                    description: description.to_string(),
+                    path: if kind == "experience" {
+                        format!("image/experience/{}", path)
+                    } else {
+                        path.to_string()
+                    },
+                    date: img.get("date").and_then(|date| date.as_datetime()).cloned(),
+                });
+            }
         }
-        _ => (0, String::from("")),
-    };
-    let homepage_offset = match state.stage {
-        Stage::ChoosingCharacter(choosing) => choosing.homepage_offset,
-        _ => return Err(crate::Error::APIError),
-    };
+    }
+    photos
+}
+
+/// 读取本地的照片，没有就先下载下来。
+pub async fn read_or_download(storage: &str, url_prefix: &str, path: &str) -> Option<Vec<u8>> {
+    let local = format!("{}{}", storage, path);
+    if let Ok(bytes) = fs::read(&local) {
+        return Some(bytes);
+    }
+    let bytes = reqwest::get(format!("{}{}", url_prefix, path))
+        .await
+        .ok()?
+        .bytes()
+        .await
+        .ok()?;
+    if let Some(parent) = std::path::Path::new(&local).parent() {
+        fs::create_dir_all(parent).ok()?;
+    }
+    let mut file = fs::File::create(&local).ok()?;
+    file.write_all(&bytes).ok()?;
+    Some(bytes.to_vec())
+}
+
+/// 每两个人一共同框过几张照片（不算没有写 `with` 的全班合照）。
+pub fn pair_counts(queue_table: &toml::Table, people: &[usize]) -> Vec<(usize, usize, usize)> {
+    let mut counts = vec![];
+    for (index, &a) in people.iter().enumerate() {
+        for &b in &people[index + 1..] {
+            counts.push((a, b, 0));
+        }
+    }
+    for kind in ["experience", "event"] {
+        for event in queue_table.get(kind).unwrap().as_array().unwrap() {
+            for img in event.get("image").unwrap().as_array().unwrap() {
+                let Some(with) = with_of(img) else {
+                    continue;
+                };
+                for (a, b, count) in counts.iter_mut() {
+                    if with.contains(a) && with.contains(b) {
+                        *count += 1;
+                    }
+                }
+            }
+        }
+    }
+    counts
+}
+
+/// 从 events.toml 中挑出 `company` 的经历。会读本地照片的 EXIF，在异步任务中请放进
+/// `spawn_blocking`。
+pub fn collect_events(storage: &str, queue_table: &toml::Table, company: &Company) -> Vec<Event> {
+    let mut queue_event = Vec::<Event>::with_capacity(queue_table.len());
     let queue_array = queue_table.get("event").unwrap().as_array().unwrap();
     let experience_array = queue_table.get("experience").unwrap().as_array().unwrap();
-    let mut rng = rand::thread_rng();
     for exp in experience_array {
         let cur_exp = exp.as_table().unwrap();
         let experience = cur_exp.get("image").unwrap().as_array().unwrap();
         let mut images = Vec::<Experience>::with_capacity(experience.len());
         for img in experience {
-            let img_shotdate = img.get("date").unwrap().as_datetime().unwrap().into();
-            let Some(with_people) = img.get("with") else {
+            let Some(with) = with_of(img) else {
+                let path = img.get("path").unwrap().as_str().unwrap().to_string();
                 images.push(Experience {
-                    path: img.get("path").unwrap().as_str().unwrap().to_string(),
-                    shot: img_shotdate,
+                    shot: shot_time(img, storage, &path),
+                    path,
                     handle: None,
+                    animation: None,
+                    failed: None,
                     join_handle: Arc::new(Mutex::new(None)),
+                    with: vec![],
+                    regions: parse_regions(img),
                 });
                 continue;
             };
-            let with_people = with_people.as_array().unwrap();
-            let mut with = Vec::with_capacity(with_people.len());
-            for i in with_people {
-                with.push(i.as_integer().unwrap() as usize);
-            }
-            if with.contains(&chose_person) {
+            if company.matches(&with) {
+                let path = format!(
+                    "image/experience/{}",
+                    img.get("path").unwrap().as_str().unwrap()
+                );
                 images.push(Experience {
-                    path: format!(
-                        "image/experience/{}",
-                        img.get("path").unwrap().as_str().unwrap()
-                    ),
-                    shot: img_shotdate,
+                    shot: shot_time(img, storage, &path),
+                    path,
                     handle: None,
+                    animation: None,
+                    failed: None,
                     join_handle: Arc::new(Mutex::new(None)),
+                    with,
+                    regions: parse_regions(img),
                 });
                 break;
             }
@@ -240,7 +440,7 @@ pub async fn get_queue(state: State) -> Result<State, crate::Error> {
             for index in (0..images.len() - 1).rev() {
                 if images[index] != images[index + 1] {
                     let mut shuffling = images.split_off(index + 1);
-                    shuffling.shuffle(&mut rng);
+                    shuffling.shuffle(&mut rand::thread_rng());
                     queue_event.push(Event {
                         description: description.clone(),
                         experiences: shuffling,
@@ -255,33 +455,37 @@ pub async fn get_queue(state: State) -> Result<State, crate::Error> {
         let experience = cur_table.get("image").unwrap().as_array().unwrap();
         let mut images = Vec::<Experience>::with_capacity(experience.len());
         for img in experience {
-            let img_shotdate = img.get("date").unwrap().as_datetime().unwrap().into();
-            let Some(with_people) = img.get("with") else {
+            let Some(with) = with_of(img) else {
+                let path = img.get("path").unwrap().as_str().unwrap().to_string();
                 images.push(Experience {
-                    path: img.get("path").unwrap().as_str().unwrap().to_string(),
-                    shot: img_shotdate,
+                    shot: shot_time(img, storage, &path),
+                    path,
                     handle: None,
+                    animation: None,
+                    failed: None,
                     join_handle: Arc::new(Mutex::new(None)),
+                    with: vec![],
+                    regions: parse_regions(img),
                 });
                 continue;
             };
-            let with_people = with_people.as_array().unwrap();
-            let mut with = Vec::with_capacity(with_people.len());
-            for i in with_people {
-                with.push(i.as_integer().unwrap() as usize);
-            }
-            if with.contains(&chose_person) {
+            if company.matches(&with) {
+                let path = img.get("path").unwrap().as_str().unwrap().to_string();
                 images.push(Experience {
-                    path: img.get("path").unwrap().as_str().unwrap().to_string(),
-                    shot: img_shotdate,
+                    shot: shot_time(img, storage, &path),
+                    path,
                     handle: None,
+                    animation: None,
+                    failed: None,
                     join_handle: Arc::new(Mutex::new(None)),
+                    with,
+                    regions: parse_regions(img),
                 });
                 break;
             }
         }
         if !images.is_empty() {
-            images.shuffle(&mut rng);
+            images.shuffle(&mut rand::thread_rng());
             queue_event.push(Event {
                 description: cur_table
                     .get("description")
@@ -294,42 +498,143 @@ pub async fn get_queue(state: State) -> Result<State, crate::Error> {
             });
         }
     }
+    queue_event
+}
+
+pub async fn get_queue(state: State) -> Result<State, crate::Error> {
+    let queue_table = read_events(&state).await;
+    let (company, character_name) = match state.stage {
+        Stage::ChoosingCharacter(ref choosing) => match choosing.on_character {
+            Some(on) => (Company::alone(on), choosing.avatars[on].name.clone()),
+            None => {
+                let names: Vec<String> = choosing
+                    .companions
+                    .iter()
+                    .map(|&person| choosing.avatars[person].name.clone())
+                    .collect();
+                (
+                    Company {
+                        people: choosing.companions.clone(),
+                        require_all: choosing.require_all,
+                    },
+                    names.join("、"),
+                )
+            }
+        },
+        _ => (Company::default(), String::from("")),
+    };
+    fs::create_dir_all(format!("{}/image/experience", state.storage)).unwrap();
+    fs::create_dir_all(format!("{}/image/camera", state.storage)).unwrap();
+    let pair_counts = pair_counts(&queue_table, &company.people);
+    let mut queue_event = tokio::task::spawn_blocking({
+        let storage = state.storage.clone();
+        let company = company.clone();
+        move || collect_events(&storage, &queue_table, &company)
+    })
+    .await?;
+    let homepage_offset = match state.stage {
+        Stage::ChoosingCharacter(choosing) => choosing.homepage_offset,
+        _ => return Err(crate::Error::APIError),
+    };
     queue_event.sort_unstable();
     let initial_event = Event {
         description: String::from(""),
         on_experience: 0,
         experiences: vec![Experience {
-            shot: state.configs.from_date.clone(),
+            shot: Some(state.configs.from_date.clone()),
             path: String::from(""),
             handle: None,
+            animation: None,
+            failed: None,
             join_handle: Arc::new(Mutex::new(None)),
+            with: vec![],
+            regions: vec![],
         }],
     };
     let on_event = queue_event.partition_point(|event| event < &initial_event);
-    fs::create_dir_all(format!("{}/image/experience", state.storage)).unwrap();
-    fs::create_dir_all(format!("{}/image/camera", state.storage)).unwrap();
     let mut state = State {
         stage: Stage::ShowingPlots(crate::VisitingState {
             homepage_offset,
             character_name,
             events: Arc::new(Mutex::new(queue_event)),
             on_event,
+            company,
+            pair_counts,
+            slideshow: Default::default(),
+            notice: String::new(),
         }),
         ..state
     };
     load_images(&mut state);
+    wait_for_current(&state).await?;
     Ok(state)
 }
 
+/// 第一张图片要等它解码完再显示。
+pub async fn wait_for_current(state: &State) -> Result<(), crate::Error> {
+    if let Stage::ShowingPlots(ref displayer) = state.stage {
+        let join_handle = {
+            let events = displayer.events.lock().unwrap();
+            events
+                .get(displayer.on_event)
+                .map(|event| event.get_join_handle())
+        };
+        if let Some(join_handle) = join_handle {
+            let t = std::mem::take(&mut *join_handle.lock().unwrap());
+            if let Some(t) = t {
+                t.await?;
+            }
+        }
+    }
+    Ok(())
+}
+
 pub async fn force_load(
     join_handle: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
     memo: Memories,
 ) -> Result<Memories, crate::Error> {
     let a: Option<tokio::task::JoinHandle<()>> = std::mem::take(&mut join_handle.lock().unwrap());
-    a.unwrap().await.unwrap();
+    if let Some(a) = a {
+        a.await?;
+    }
     Ok(memo)
 }
 
+/// 解码本地的图片，没有的话先下载；出错时返回给用户看的原因。
+async fn fetch_and_decode(
+    img_dir: String,
+    url: String,
+) -> Result<(image::Handle, Option<decoding::Animation>), String> {
+    if std::path::Path::new(&img_dir).is_file() {
+        return tokio::task::spawn_blocking(move || {
+            let animation = if decoding::may_be_animated(&img_dir) {
+                decoding::decode_animation(&fs::read(&img_dir).map_err(|e| e.to_string())?)
+            } else {
+                None
+            };
+            Ok((decoding::decode(&img_dir), animation))
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    let bytes = reqwest::get(&url)
+        .await
+        .map_err(|e| e.to_string())?
+        .bytes()
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut file = fs::File::create(&img_dir).map_err(|e| e.to_string())?;
+    file.write_all(&bytes).map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        (
+            decoding::decode_bytes(&img_dir, &bytes),
+            decoding::decode_animation(&bytes),
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
 pub fn load_images(state: &mut State) {
     match state.stage {
         Stage::ShowingPlots(ref displayer) => {
@@ -345,29 +650,50 @@ pub fn load_images(state: &mut State) {
                     0
                 }
             };
-            for cur_idx in left..std::cmp::min(displayer.on_event + 5, len) {
+            let right = std::cmp::min(displayer.on_event + 5, len);
+            {
+                // 离得远的图片先放掉，等走近了再重新解码
+                let mut events = displayer.events.lock().unwrap();
+                for cur_idx in (0..left).chain(right..len) {
+                    for experience in events[cur_idx].experiences.iter_mut() {
+                        let mut join_handle = experience.join_handle.lock().unwrap();
+                        let finished = match join_handle.as_ref() {
+                            Some(t) => t.is_finished(),
+                            None => true,
+                        };
+                        if finished {
+                            *join_handle = None;
+                            experience.handle = None;
+                            experience.animation = None;
+                            experience.failed = None;
+                        }
+                    }
+                }
+            }
+            for cur_idx in left..right {
                 let mut events = displayer.events.lock().unwrap();
                 for (cur_img, experience) in events[cur_idx].experiences.iter_mut().enumerate() {
-                    let need_to_load = match *experience.join_handle.lock().unwrap() {
-                        None => true,
-                        _ => false,
-                    };
+                    let need_to_load = experience.handle.is_none()
+                        && experience.failed.is_none()
+                        && match *experience.join_handle.lock().unwrap() {
+                            None => true,
+                            _ => false,
+                        };
                     if need_to_load {
-                        // let path = experience.path;
                         let img_dir = format!("{}{}", &state.storage, experience.path);
-                        let img_path = std::path::Path::new(&img_dir);
-                        if img_path.is_file() {
-                            experience.handle = Some(image::Handle::from_path(&img_dir));
-                            continue;
-                        }
                         let url = format!("{}{}", location, experience.path);
                         let given_mutex = displayer.events.clone();
                         let t = tokio::spawn(async move {
-                            let bytes = reqwest::get(&url).await.unwrap().bytes().await.unwrap();
-                            let mut file = std::fs::File::create(&img_dir).unwrap();
-                            file.write_all(&bytes).unwrap();
-                            given_mutex.lock().unwrap()[cur_idx].experiences[cur_img].handle =
-                                Some(image::Handle::from_memory(bytes));
+                            let loaded = fetch_and_decode(img_dir, url).await;
+                            let mut events = given_mutex.lock().unwrap();
+                            let experience = &mut events[cur_idx].experiences[cur_img];
+                            match loaded {
+                                Ok((handle, animation)) => {
+                                    experience.handle = Some(handle);
+                                    experience.animation = animation;
+                                }
+                                Err(err) => experience.failed = Some(err),
+                            }
                         });
                         *experience.join_handle.lock().unwrap() = Some(t);
                     }
@@ -377,3 +703,33 @@ pub fn load_images(state: &mut State) {
         _ => (),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pair_counts_skip_whole_class_photos() {
+        let queue_table: toml::Table = toml::from_str(
+            r#"
+            [[experience]]
+            description = "春游"
+            image = [
+                { path = "a.jpg", with = [1, 2] },
+                { path = "b.jpg", with = [1, 2, 3] },
+                { path = "c.jpg" },
+            ]
+
+            [[event]]
+            description = "运动会"
+            image = [{ path = "d.jpg", with = [2, 3] }]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            pair_counts(&queue_table, &[1, 2, 3]),
+            vec![(1, 2, 2), (1, 3, 1), (2, 3, 2)]
+        );
+        assert!(pair_counts(&queue_table, &[1]).is_empty());
+    }
+}
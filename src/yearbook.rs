@@ -0,0 +1,236 @@
+use crate::choosing::Profile;
+use crate::export::{self, Destination};
+use crate::{site, visiting, Stage, State};
+use base64::Engine;
+use std::fmt::Write;
+
+/// 每页最多附上几张照片
+const MAX_PHOTOS: usize = 6;
+/// 嵌入的图片最长边，够打印一页 A4
+const MAX_SIDE: u32 = 1200;
+
+pub const STYLE: &str = "
+body { font-family: sans-serif; max-width: 800px; margin: 0 auto; padding: 20px; color: #222; }
+header { display: flex; align-items: center; gap: 24px; }
+header img { width: 160px; height: 160px; object-fit: cover; border-radius: 8px; }
+h1 { margin: 0; font-size: 40px; }
+h2 { border-bottom: 2px solid #789ecc; padding-bottom: 4px; margin-top: 28px; }
+.term { color: #789ecc; margin-bottom: 0; }
+.quote { border-left: 4px solid #f08650; padding-left: 12px; }
+.photos { display: grid; grid-template-columns: 1fr 1fr; gap: 12px; }
+.photos figure { margin: 0; page-break-inside: avoid; }
+.photos img { width: 100%; border-radius: 6px; }
+@media print { body { padding: 0; } }
+";
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "<br/>")
+}
+
+pub fn mime_of(path: &str) -> &'static str {
+    match path
+        .rsplit('.')
+        .next()
+        .map(|ext| ext.to_lowercase())
+        .as_deref()
+    {
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        _ => "image/jpeg",
+    }
+}
+
+/// 把图片嵌进网页里，这样一个文件就能单独打开和打印。
+pub fn data_url(mime: &str, bytes: &[u8]) -> String {
+    format!(
+        "data:{};base64,{}",
+        mime,
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    )
+}
+
+fn list(html: &mut String, title: &str, items: &[String]) {
+    if items.is_empty() {
+        return;
+    }
+    write!(html, "<h2>{}</h2><ul>", title).unwrap();
+    for item in items {
+        write!(html, "<li>{}</li>", escape(item)).unwrap();
+    }
+    html.push_str("</ul>");
+}
+
+/// 同学录的一页，照片为 (说明, 图片地址)；输出同时是合法的 XHTML，电子书也能用。
+pub fn page(
+    name: &str,
+    names: &[String],
+    profile: &Profile,
+    avatar: Option<String>,
+    photos: &[(String, String)],
+) -> String {
+    let mut html = String::new();
+    html.push_str("<header>");
+    if let Some(avatar) = avatar {
+        write!(html, "<img src=\"{}\" alt=\"\"/>", avatar).unwrap();
+    }
+    write!(html, "<div><h1>{}</h1>", escape(name)).unwrap();
+    if let Some(name_en) = &profile.name_en {
+        write!(html, "<p>{}</p>", escape(name_en)).unwrap();
+    }
+    html.push_str("</div></header>");
+    html.push_str(&details(names, profile, photos));
+    html
+}
+
+/// [`page`] 中标题以下的部分：昵称、身份、自传、评语和照片。
+pub fn details(names: &[String], profile: &Profile, photos: &[(String, String)]) -> String {
+    let mut html = String::new();
+    list(&mut html, "ta 的昵称", &profile.nickname);
+    let relations: Vec<String> = profile
+        .relationship
+        .iter()
+        .map(|relation| format!("是 {} 的 {}", names[relation.by], relation.is))
+        .collect();
+    list(&mut html, "ta 的身份", &relations);
+    if let Some(anecdote) = &profile.anecdote {
+        let answers: Vec<String> = [
+            ("兴趣爱好", &anecdote.interests),
+            ("最想做的事", &anecdote.want),
+            ("最尴尬的事", &anecdote.embarrassment),
+        ]
+        .into_iter()
+        .filter_map(|(question, answer)| Some(format!("{}：{}", question, answer.as_ref()?)))
+        .collect();
+        list(&mut html, "关于 ta", &answers);
+    }
+    if let Some(intro) = &profile.introduction {
+        write!(html, "<h2>ta 的自传</h2><p>{}</p>", escape(intro)).unwrap();
+    }
+    if !profile.reviews.is_empty() {
+        html.push_str("<h2>班主任评语</h2>");
+        for review in &profile.reviews {
+            write!(
+                html,
+                "<p class=\"term\">{}</p><p>{}</p>",
+                escape(&review.term),
+                escape(&review.content)
+            )
+            .unwrap();
+        }
+    }
+    if !profile.comment.is_empty() {
+        html.push_str("<h2>大家对 ta 的评价</h2>");
+        for comment in &profile.comment {
+            let date = comment
+                .date
+                .map(|date| format!("，于 {}", date))
+                .unwrap_or_default();
+            write!(
+                html,
+                "<p class=\"quote\">{}<br/><small>来自 {}{}</small></p>",
+                escape(&comment.description),
+                escape(&names[comment.from]),
+                date
+            )
+            .unwrap();
+        }
+    }
+    if !photos.is_empty() {
+        html.push_str("<h2>一起的时光</h2><div class=\"photos\">");
+        for (description, src) in photos {
+            write!(
+                html,
+                "<figure><img src=\"{}\" alt=\"\"/><figcaption>{}</figcaption></figure>",
+                src,
+                escape(description)
+            )
+            .unwrap();
+        }
+        html.push_str("</div>");
+    }
+    html
+}
+
+pub fn document(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}\n</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        body
+    )
+}
+
+/// 缩小成 jpg 再嵌进网页，打印用不着原图那么大；解不开的原样嵌入。
+async fn embed(path: String, bytes: Vec<u8>) -> String {
+    tokio::task::spawn_blocking(move || match site::shrink(&bytes, MAX_SIDE) {
+        Some(shrunk) => data_url("image/jpeg", &shrunk),
+        None => data_url(mime_of(&path), &bytes),
+    })
+    .await
+    .unwrap()
+}
+
+/// 第 `num` 位同学的头像和几张照片，都转成 data url。
+pub async fn pictures_of(
+    state: &State,
+    queue_table: &toml::Table,
+    num: usize,
+) -> (Option<String>, Vec<(String, String)>) {
+    let url_prefix = state.idxtable.get("url_prefix").unwrap().as_str().unwrap();
+    let avatar_path = format!("{}/image/known_people/{}.jpg", state.storage, num);
+    let avatar = match std::fs::read(&avatar_path) {
+        Ok(bytes) => Some(embed(avatar_path, bytes).await),
+        Err(_) => None,
+    };
+    let mut photos = vec![];
+    for photo in visiting::photos_of(queue_table, num) {
+        if photos.len() == MAX_PHOTOS {
+            break;
+        }
+        if let Some(bytes) =
+            visiting::read_or_download(&state.storage, url_prefix, &photo.path).await
+        {
+            photos.push((photo.description, embed(photo.path, bytes).await));
+        }
+    }
+    (avatar, photos)
+}
+
+pub fn names_of(state: &State) -> Vec<String> {
+    match &state.stage {
+        Stage::ChoosingCharacter(choosing) => choosing
+            .avatars
+            .iter()
+            .map(|avatar| avatar.name.clone())
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// 把当前同学的资料、头像和几张照片写成一个能直接打印的网页。
+pub async fn export(state: State, num: usize) -> String {
+    let profile = match &state.stage {
+        Stage::ChoosingCharacter(choosing) => choosing.profiles[num].clone(),
+        _ => return String::new(),
+    };
+    let names = names_of(&state);
+    let name = names[num].clone();
+    let destination = Destination::File {
+        title: "导出同学录页面",
+        name: format!("{}.html", name),
+        filter: ("网页", &["html"]),
+    };
+    export::save_with_notice(destination, |path| async move {
+        let queue_table = visiting::read_events(&state).await;
+        let (avatar, photos) = pictures_of(&state, &queue_table, num).await;
+        let html = document(&name, &page(&name, &names, &profile, avatar, &photos));
+        std::fs::write(path, html).map_err(|e| e.to_string())?;
+        Ok::<_, String>(String::from("，用浏览器打开即可打印"))
+    })
+    .await
+}